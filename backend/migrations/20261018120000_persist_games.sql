@@ -0,0 +1,16 @@
+-- games were never written before this, so the new columns don't need defaults
+-- for existing rows
+
+ALTER TABLE games
+    ALTER COLUMN creator_id DROP NOT NULL,
+    ALTER COLUMN creator_id TYPE uuid USING creator_id::uuid,
+    ADD COLUMN game_id uuid NOT NULL UNIQUE,
+    ADD COLUMN options jsonb NOT NULL,
+    ADD COLUMN picks integer[] NOT NULL DEFAULT '{}',
+    ADD COLUMN active boolean NOT NULL DEFAULT true;
+
+CREATE INDEX games_active ON games (active);
+
+ALTER TABLE players
+    ADD COLUMN seed bigint,
+    ADD CONSTRAINT players_user_id_game_id UNIQUE (user_id, game_id);
@@ -0,0 +1,6 @@
+-- games saved before this were all running already
+ALTER TABLE games
+    ADD COLUMN state text NOT NULL DEFAULT 'running';
+
+ALTER TABLE games
+    ALTER COLUMN state DROP DEFAULT;
@@ -0,0 +1,5 @@
+-- cells each player marked on their board, rows saved before this have none
+-- marked
+
+ALTER TABLE players
+    ADD COLUMN marks boolean[];
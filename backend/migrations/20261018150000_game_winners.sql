@@ -0,0 +1,4 @@
+-- everyone who won a game so far, as a list of `Winner`s
+
+ALTER TABLE games
+    ADD COLUMN winners jsonb NOT NULL DEFAULT '[]';
@@ -0,0 +1,5 @@
+-- the rounds played before the current one, as a list of `Round`s, the number
+-- of the current round is one more than their count
+
+ALTER TABLE games
+    ADD COLUMN rounds jsonb NOT NULL DEFAULT '[]';
@@ -0,0 +1,5 @@
+-- items suggested by players, as a list of `Suggestion`s, approved ones are
+-- also in `items`
+
+ALTER TABLE games
+    ADD COLUMN suggestions jsonb NOT NULL DEFAULT '[]';
@@ -0,0 +1,4 @@
+-- shown to players, games without one are simply untitled
+
+ALTER TABLE games
+    ADD COLUMN title text;
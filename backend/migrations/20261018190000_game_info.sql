@@ -0,0 +1,10 @@
+-- what a game is about, next to its title, tags are indexed so games can be
+-- listed by them
+
+ALTER TABLE games
+    ADD COLUMN description text,
+    ADD COLUMN cover_url text,
+    ADD COLUMN language text,
+    ADD COLUMN tags text[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX games_tags ON games USING gin (tags);
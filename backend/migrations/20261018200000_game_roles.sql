@@ -0,0 +1,5 @@
+-- roles granted by the owner of a game, as an object of user ULIDs to `Role`s,
+-- users who aren't in it are players
+
+ALTER TABLE games
+    ADD COLUMN roles jsonb NOT NULL DEFAULT '{}';
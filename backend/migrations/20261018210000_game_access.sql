@@ -0,0 +1,7 @@
+-- who can join a game, private ones take their invite code or password, which
+-- is only kept as an argon2 hash
+
+ALTER TABLE games
+    ADD COLUMN visibility text NOT NULL DEFAULT 'public',
+    ADD COLUMN invite_code text,
+    ADD COLUMN password_hash text;
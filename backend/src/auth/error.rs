@@ -12,6 +12,8 @@ pub enum TwitchAuthError {
     AuthorizationDenied,
     #[error("error when querying stuff from the database")]
     DatabaseError,
+    #[error("something went wrong on our side")]
+    Internal,
     #[error("twitch returned an invalid response: {0}")]
     #[serde(untagged)]
     BadResponseFromTwitch(&'static str),
@@ -47,6 +49,7 @@ impl<T: std::error::Error + Sync + Send> From<ClientRequestError<T>> for TwitchA
 
 impl From<sqlx::Error> for TwitchAuthError {
     fn from(value: sqlx::Error) -> Self {
+        error!("failed querying the database: {value:?}");
         Self::DatabaseError
     }
 }
@@ -8,10 +8,12 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use ulid::Ulid;
 
+#[allow(clippy::expect_used)]
 static JWT_SECRET: Lazy<(EncodingKey, DecodingKey)> = Lazy::new(|| {
     #[cfg(debug_assertions)]
     {
-        let secret = base64::prelude::BASE64_STANDARD.decode(dotenvy::var("JWT_SECRET").unwrap()).unwrap();
+        let secret = dotenvy::var("JWT_SECRET").expect("JWT_SECRET must be set");
+        let secret = base64::prelude::BASE64_STANDARD.decode(secret).expect("JWT_SECRET must be base64");
         (
             EncodingKey::from_secret(&secret),
             DecodingKey::from_secret(&secret)
@@ -65,8 +67,19 @@ impl Claims {
             user_kind
         }
     }
+
+    pub fn user_id(&self) -> Ulid {
+        self.user_id
+    }
+
+    pub fn user_kind(&self) -> &UserKind {
+        &self.user_kind
+    }
 }
 
+// encoding only fails for keys that don't fit the algorithm, which a
+// secret for HMAC always does
+#[allow(clippy::expect_used)]
 pub fn create_new_jwt(claims: Claims) -> String {
     jsonwebtoken::encode(&Header::default(), &claims, &JWT_SECRET.0)
         .expect("failed to encode JWT token")
@@ -13,6 +13,7 @@ use actix_web::{
     HttpResponseBuilder
 };
 use chrono::Utc;
+use log::error;
 pub use middleware::TwitchAuthMiddleware;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
@@ -23,7 +24,7 @@ use crate::{app_info::AppInfo, auth::jwt::{create_new_jwt, Claims}, user::{self,
 
 use self::error::TwitchAuthError;
 
-const JULIALUXEL_TWITCH_ID: &'static str = "173685614";
+const JULIALUXEL_TWITCH_ID: &str = "173685614";
 
 /// query params twitch sends us in case the user allowed authorization
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,7 +84,9 @@ pub async fn twitch_auth(
 
             let token = token_builder.get_user_token(&client, "juh", &success.code).await?;
 
-            let display_name = client.get_user_from_id(&token.user_id, &token).await?.unwrap().display_name;
+            let display_name = client.get_user_from_id(&token.user_id, &token).await?
+                .ok_or(TwitchAuthError::BadResponseFromTwitch("twitch didn't return the user that just logged in"))?
+                .display_name;
 
             let expiration = token.expires_in();
 
@@ -96,10 +99,15 @@ pub async fn twitch_auth(
                 token.access_token,
                 now,
                 now + expiration,
-                token.refresh_token.unwrap()
+                token.refresh_token.ok_or(TwitchAuthError::BadResponseFromTwitch("twitch didn't return a refresh token"))?
             ).upsert_for_ulid(user.user_id.into()).execute(&**db_pool).await?;
 
-            let jwt = tokio::task::spawn_blocking(move || {create_new_jwt(Claims::new(user.user_id.into(), jwt::UserKind::Player, expiration))}).await.unwrap();
+            let jwt = tokio::task::spawn_blocking(move || {create_new_jwt(Claims::new(user.user_id.into(), jwt::UserKind::Player, expiration))})
+                .await
+                .map_err(|e| {
+                    error!("failed creating a JWT: {e}");
+                    TwitchAuthError::Internal
+                })?;
 
             let cookie = CookieBuilder::new("jwt", jwt)
                 .http_only(true)
@@ -59,9 +59,14 @@ pub struct Arguments {
     /// - 2 -> TRACE
     #[arg(short, action(ArgAction::Count), env="BINGO_VERBOSITY")]
     pub verbose: u8,
+    /// how many spectators can watch a single game at once
+    #[arg(long, env="MAX_SPECTATORS", default_value = "1000")]
+    pub max_spectators: usize,
     #[command(flatten)]
     pub pg_args: PgArgs,
     #[command(flatten)]
+    pub expiry_args: ExpiryArgs,
+    #[command(flatten)]
     pub app_info: AppInfo
 }
 
@@ -78,3 +83,17 @@ pub struct PgArgs {
     #[arg(long, env="PG_USERNAME", default_value = "postgres")]
     pub username: String,
 }
+
+#[derive(Args)]
+pub struct ExpiryArgs {
+    /// seconds a game can go without any activity or connected websockets
+    /// besides spectators before it is removed
+    #[arg(long, env="GAME_IDLE_TIMEOUT", default_value = "3600")]
+    pub idle_timeout: u64,
+    /// seconds after its creation a game is ended and removed, no matter what
+    #[arg(long, env="GAME_MAX_LIFETIME", default_value = "86400")]
+    pub max_lifetime: u64,
+    /// seconds between checks for games to remove
+    #[arg(long, env="GAME_SWEEP_INTERVAL", default_value = "60")]
+    pub sweep_interval: u64,
+}
@@ -1,5 +1,5 @@
 use utoipa::{Modify, OpenApi};
-use crate::{game::{create, get, update}, websocket, auth};
+use crate::{game::{access, board, claim, create, error, get, info, join, lifecycle, mark, round, score, options, pick, role, suggest, update, vote, win}, websocket, auth};
 
 
 #[derive(OpenApi)]
@@ -10,6 +10,31 @@ use crate::{game::{create, get, update}, websocket, auth};
     paths(
         create::create_game,
         get::get_game,
+        update::update_game,
+        join::join_game,
+        board::get_board,
+        pick::pick_item,
+        pick::unpick_item,
+        pick::replace_pick,
+        claim::claim_bingo,
+        mark::mark_cell,
+        suggest::suggest_item,
+        suggest::get_suggestions,
+        suggest::review_suggestion,
+        access::update_access,
+        access::get_invite,
+        access::rotate_invite,
+        access::resolve_invite,
+        role::get_roles,
+        role::grant_role,
+        role::revoke_role,
+        vote::open_poll,
+        lifecycle::start_game,
+        lifecycle::pause_game,
+        lifecycle::resume_game,
+        lifecycle::end_game,
+        round::new_round,
+        score::get_leaderboard,
         websocket::websocket,
         auth::twitch_auth
     ),
@@ -18,9 +43,24 @@ use crate::{game::{create, get, update}, websocket, auth};
             crate::game::Item,
             get::GameRequest, get::GameData,
             create::CreateGameRequest, create::CreatedGame, create::CreateError,
+            update::UpdateGameRequest, update::UpdateError,
+            info::GameInfo,
+            board::BoardData, board::Cell,
+            pick::PickRequest, pick::ReplaceRequest,
+            mark::MarkRequest,
+            error::GameError,
+            options::GameOptions, options::WinMode,
+            lifecycle::GameState,
+            round::Round,
+            score::Scoring, score::LeaderboardEntry,
+            suggest::Suggestion, suggest::SuggestionStatus, suggest::SuggestRequest, suggest::ReviewRequest,
+            role::Role, role::GrantRequest, role::RoleEntry,
+            access::Visibility, access::AccessRequest, access::Invite, access::InvitedGame,
+            vote::Voting, vote::OpenedPoll,
+            win::Line, win::WinPattern, win::Win, win::Winner,
             websocket::WsRequestError
         ),
-        responses(create::CreatedGame, get::GameData)
+        responses(create::CreatedGame, get::GameData, board::BoardData)
     ),
     tags(
         (name = "Game", description = "endpoints that control the game cycle"),
@@ -1,21 +1,215 @@
 use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::game::{lifecycle::GameState, role::Role, score::ScoreChange, suggest::Suggestion, win::{Line, RevokeReason, WinPattern}};
+
+/// a [ServerEvent] along with the round of the game it happened in, this is
+/// what gets sent to clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameEvent {
+    pub round: u32,
+    pub event: ServerEvent,
+}
 
-// TODO: make this do things
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ServerEvent {
     NewBall {
         idx: usize,
     },
+    /// the next item will be picked automatically in `seconds`, sent whenever
+    /// a countdown starts or resumes
+    Countdown {
+        seconds: u64,
+    },
+    /// the host took back the pick of the item at `idx`
+    Unpicked {
+        idx: usize,
+    },
+    /// a player completed one of the game's winning patterns, sent right
+    /// before [ServerEvent::GameOver] if that ended the round
+    Winner {
+        player: Ulid,
+        pattern: WinPattern,
+        /// which line was completed when `pattern` is [WinPattern::Line]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line: Option<Line>,
+    },
+    /// a player's win doesn't count anymore, sent after the
+    /// [ServerEvent::Unpicked] that caused it
+    WinRevoked {
+        player: Ulid,
+        pattern: WinPattern,
+        /// which line isn't complete anymore when `pattern` is
+        /// [WinPattern::Line]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line: Option<Line>,
+        reason: RevokeReason,
+    },
+    /// the scores of some players changed, sent after every
+    /// [ServerEvent::Winner] and [ServerEvent::WinRevoked]
+    ScoresChanged {
+        changes: Vec<ScoreChange>,
+    },
+    /// the host edited the game in the lobby, clients should fetch it again
+    GameUpdated,
+    /// a player suggested an item, which now waits for the host to review it
+    Suggested {
+        suggestion: Suggestion,
+    },
+    /// the host reviewed a suggestion, approved ones are added to the items
+    /// at `idx`
+    SuggestionReviewed {
+        suggestion: Ulid,
+        approved: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        idx: Option<usize>,
+    },
+    /// the host opened a vote on whether the item at `idx` should be picked,
+    /// it closes after `seconds` if it doesn't get `needed` yes votes by then
+    PollOpened {
+        poll: Ulid,
+        idx: usize,
+        needed: u32,
+        seconds: u64,
+    },
+    /// the votes so far in a poll, sent after every vote
+    VoteTally {
+        poll: Ulid,
+        yes: u32,
+        no: u32,
+        needed: u32,
+    },
+    /// a poll was decided, either by picking its item, which is sent right
+    /// after the [ServerEvent::NewBall], or by running out of time
+    PollClosed {
+        poll: Ulid,
+        idx: usize,
+        picked: bool,
+    },
+    /// the owner gave a user another role in the game, [Role::Player] means
+    /// they lost the one they had
+    RoleChanged {
+        user: Ulid,
+        role: Role,
+    },
+    /// a player claimed a bingo they didn't have, sent only to them and at
+    /// most once per player between two picks
+    ClaimRejected {
+        player: Ulid,
+    },
+    /// a new round started, with every pick and mark cleared, sent right
+    /// before the game changes to [GameState::Running]
+    NewRound,
+    /// the game moved to another state, sent on every transition
+    StateChanged {
+        state: GameState,
+    },
+    /// the game is finished, sent right after it changes to
+    /// [GameState::Finished]
     GameOver,
     Reconnect {
         delay: u32
     },
 }
 
-// TODO: add client-sent events
+impl ServerEvent {
+    /// whether spectators get the event, they only see what happens on the
+    /// board and not what players and hosts do around it
+    pub fn for_spectators(&self) -> bool {
+        match self {
+            ServerEvent::NewBall { .. }
+            | ServerEvent::Countdown { .. }
+            | ServerEvent::Unpicked { .. }
+            | ServerEvent::Winner { .. }
+            | ServerEvent::WinRevoked { .. }
+            | ServerEvent::ScoresChanged { .. }
+            | ServerEvent::GameUpdated
+            | ServerEvent::NewRound
+            | ServerEvent::StateChanged { .. }
+            | ServerEvent::GameOver
+            | ServerEvent::Reconnect { .. } => true,
+            ServerEvent::Suggested { .. }
+            | ServerEvent::SuggestionReviewed { .. }
+            | ServerEvent::PollOpened { .. }
+            | ServerEvent::VoteTally { .. }
+            | ServerEvent::PollClosed { .. }
+            | ServerEvent::RoleChanged { .. }
+            | ServerEvent::ClaimRejected { .. } => false,
+        }
+    }
+
+    /// the only user the event is sent to, if it's about them alone
+    pub fn recipient(&self) -> Option<Ulid> {
+        match self {
+            ServerEvent::ClaimRejected { player } => Some(*player),
+            _ => None,
+        }
+    }
+}
+
+/// a request sent by a client over the websocket
+///
+/// every request gets exactly one [Reply](crate::websocket::reply::Reply)
+/// with the same `id`, so clients can tell which request it answers
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum ClientEvent {
+pub struct ClientMessage {
+    /// chosen by the client, echoed back in the reply
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub event: ClientEvent,
+}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ClientEvent {
+    /// join the game, same as `POST /game/{id}/join`
+    Join,
+    /// mark or unmark the cell at `cell` on the player's own board
+    MarkCell {
+        cell: usize,
+        marked: bool,
+    },
+    /// suggest an item while the game is in the lobby, same as
+    /// `POST /game/{id}/suggestions`
+    Suggest {
+        text: String,
+    },
+    /// the player claims they got a bingo
+    ClaimBingo,
+    /// the host picked an item, same as `POST /game/{id}/pick`
+    PickItem {
+        idx: usize,
+    },
+    /// the host takes back a pick, same as `POST /game/{id}/unpick`
+    UnpickItem {
+        idx: usize,
+    },
+    /// the host replaces a pick, same as `POST /game/{id}/replace`
+    ReplacePick {
+        old: usize,
+        new: usize,
+    },
+    /// the host opens a vote on an item, same as `POST /game/{id}/poll`
+    OpenPoll {
+        idx: usize,
+    },
+    /// the player votes for or against picking the item of the poll `poll`
+    Vote {
+        poll: Ulid,
+        yes: bool,
+    },
+    /// the host starts the next round, same as `POST /game/{id}/round`
+    NewRound,
+    /// check that the connection is still alive
+    Ping,
+    /// start listening to the events of another game instead
+    Subscribe {
+        /// the ULID of the game or its join code
+        game: String,
+        /// the invite code or password, if the game is private
+        #[serde(default)]
+        key: Option<String>,
+    },
 }
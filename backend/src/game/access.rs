@@ -0,0 +1,266 @@
+use std::{convert::Infallible, future::{ready, Ready}, str::FromStr};
+
+use actix_web::{web::{Data, Json, Path}, FromRequest, HttpResponse};
+use argon2::{password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString}, Argon2};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{create::CreateError, error::GameError, manager::GamesManager, role::Permission, update::UpdateError};
+
+/// how many characters invite codes have
+pub const INVITE_LENGTH: usize = 8;
+pub const MAX_PASSWORD_LENGTH: usize = 64;
+/// the header the invite code or password of a private game is sent in, so
+/// it never ends up in a URL or the access logs
+pub const ACCESS_KEY_HEADER: &str = "X-Game-Key";
+
+/// who can find and join a game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum Visibility {
+    /// anyone can join, and the game can be listed
+    #[default]
+    Public,
+    /// anyone with its ULID can join, but it's never listed
+    Unlisted,
+    /// joining or watching takes the game's invite code or its password
+    Private,
+}
+
+impl Visibility {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Unlisted => "unlisted",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(Visibility::Public),
+            "unlisted" => Ok(Visibility::Unlisted),
+            "private" => Ok(Visibility::Private),
+            _ => Err(()),
+        }
+    }
+}
+
+/// how a game can be joined, nothing in here is ever sent to players
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Access {
+    pub visibility: Visibility,
+    /// issued by the [GamesManager], so games that were never added to one
+    /// don't have one
+    pub invite: Option<String>,
+    /// the argon2 hash of the password chosen by the host, as a PHC string
+    pub password_hash: Option<String>,
+}
+
+impl Access {
+    /// whether `key` is the invite code or the password of the game
+    pub fn accepts(&self, key: &str) -> bool {
+        self.invite.as_ref().is_some_and(|invite| constant_time_eq(invite.as_bytes(), super::code::normalize(key).as_bytes()))
+            || self.password_hash.as_deref().is_some_and(|hash| verify_password(key, hash))
+    }
+}
+
+/// hashes `password` to be kept in [Access::password_hash]
+pub fn hash_password(password: &str) -> Result<String, CreateError> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>()).map_err(|_| CreateError::InvalidPassword)?;
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt).map_err(|_| CreateError::InvalidPassword)?;
+    Ok(hash.to_string())
+}
+
+/// whether `password` is the one `hash` was made from
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// compares `a` and `b` without stopping at the first difference, so the time
+/// it takes doesn't tell how much of a guessed code was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// checks that `password` can be typed into a chat message, after trimming
+pub fn validate_password(password: &str) -> Result<(), CreateError> {
+    match password.chars().count() <= MAX_PASSWORD_LENGTH && !password.chars().any(char::is_control) {
+        true => Ok(()),
+        false => Err(CreateError::InvalidPassword),
+    }
+}
+
+/// the invite code or password of a private game from the
+/// [ACCESS_KEY_HEADER] header, it isn't needed by anyone who already joined or
+/// has a role in the game
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::IntoParams))]
+#[cfg_attr(feature="swagger-ui", into_params(names("X-Game-Key"), parameter_in = Header))]
+pub struct AccessKey(
+    #[cfg_attr(feature="swagger-ui", param(example = "K7PM2XQR"))]
+    pub Option<String>
+);
+
+impl FromRequest for AccessKey {
+    type Error = Infallible;
+
+    type Future = Ready<Result<AccessKey, Infallible>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _payload: &mut actix_web::dev::Payload) -> Self::Future {
+        let key = req.headers().get(ACCESS_KEY_HEADER)
+            .and_then(|key| key.to_str().ok())
+            .map(str::to_owned);
+        ready(Ok(AccessKey(key)))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct AccessRequest {
+    pub(super) visibility: Option<Visibility>,
+    /// the new password, an empty one removes it
+    #[cfg_attr(feature="swagger-ui", schema(example = "kappa123", max_length = 64))]
+    pub(super) password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Invite {
+    #[cfg_attr(feature="swagger-ui", schema(example = "K7PM2XQR"))]
+    code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct InvitedGame {
+    /// the ULID of the game
+    #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
+    id: Ulid,
+}
+
+/// change who can join the game, at any point in it
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    patch,
+    path = "/game/{id}/access",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = AccessRequest,
+    responses(
+        (status = 204, description = "The game's access was changed"),
+        (status = 400, description = "The password was too long or had control characters in it", body = UpdateError),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError)
+    )
+))]
+pub async fn update_access(
+    claims: Claims,
+    id: Path<Ulid>,
+    access: Json<AccessRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, UpdateError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    let access = access.into_inner();
+    game.set_access(access.visibility, access.password)?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
+
+/// get the current invite code of the game
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    get,
+    path = "/game/{id}/invite",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 200, description = "The invite code of the game", body = Invite),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError)
+    )
+))]
+pub async fn get_invite(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<Invite>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    let code = game.get_access().invite.ok_or(GameError::NoSuchInvite)?;
+
+    return Ok(Json(Invite { code }));
+}
+
+/// replace the invite code of the game with a new one, the old one stops
+/// working right away but everyone who already joined stays in
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/invite",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 200, description = "The new invite code of the game", body = Invite),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError)
+    )
+))]
+pub async fn rotate_invite(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<Invite>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    let code = games_manager.rotate_invite(&game);
+
+    return Ok(Json(Invite { code }));
+}
+
+/// find the game an invite code belongs to, the code is still needed to join
+/// it if it's private
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    get,
+    path = "/game/invite/{code}",
+    tag = "Game",
+    params(
+        ("code" = String, Path, description = "the invite code, in any case and with or without dashes")
+    ),
+    responses(
+        (status = 200, description = "The game the invite code belongs to", body = InvitedGame),
+        (status = 404, description = "No game has that invite code", body = GameError)
+    )
+))]
+pub async fn resolve_invite(
+    code: Path<String>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<InvitedGame>, GameError> {
+    let id = games_manager.resolve_invite(&code).ok_or(GameError::NoSuchInvite)?;
+
+    return Ok(Json(InvitedGame { id }));
+}
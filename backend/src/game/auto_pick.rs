@@ -0,0 +1,73 @@
+use std::{sync::Arc, time::Duration};
+
+use log::{debug, error};
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::event::ServerEvent;
+
+use super::{lifecycle::GameState, store::GameStore, Game};
+
+const TICK: Duration = Duration::from_secs(1);
+
+/// picks a random item every `interval` while `game` is running, like a bingo
+/// caller would, until the game is archived
+///
+/// once there's nothing left to pick the round is ended, players had the
+/// whole last countdown to claim what the last pick gave them
+///
+/// the countdown only goes on while the game is running, so pausing it keeps
+/// the time that was left, and every countdown is announced with a
+/// [ServerEvent::Countdown] once it starts or resumes
+pub async fn run(game: Arc<Game>, interval: Duration, store: Option<GameStore>) {
+    let mut ticker = tokio::time::interval_at(Instant::now() + TICK, TICK);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let total = interval.as_secs().max(1);
+    let mut remaining = total;
+    let mut announced = false;
+
+    loop {
+        ticker.tick().await;
+        match game.get_state() {
+            GameState::Running => (),
+            GameState::Archived => return,
+            GameState::Finished => {
+                // the next round starts with a full countdown
+                remaining = total;
+                announced = false;
+                continue;
+            },
+            GameState::Lobby | GameState::Paused => {
+                announced = false;
+                continue;
+            },
+        }
+
+        if !announced {
+            game.send_event(ServerEvent::Countdown { seconds: remaining });
+            announced = true;
+            continue;
+        }
+        remaining -= 1;
+        if remaining > 0 {
+            continue;
+        }
+        remaining = total;
+        announced = false;
+
+        match game.pick_random() {
+            Ok(Some(_)) => if let Some(store) = &store {
+                if let Err(e) = store.save_game(&game).await {
+                    error!("failed to save game {}: {e}", game.get_id());
+                }
+            },
+            Ok(None) => {
+                debug!("game {} has no items left to pick, ending the round", game.get_id());
+                if let Err(e) = game.end() {
+                    debug!("couldn't end game {}: {e}", game.get_id());
+                }
+            },
+            // the game stopped running since the last tick
+            Err(e) => debug!("couldn't auto pick in game {}: {e}", game.get_id()),
+        }
+    }
+}
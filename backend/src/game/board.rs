@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use actix_web::web::{Data, Json, Path};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager, playerdata::PlayerData, Game};
+
+/// a single cell of a player's board
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Cell {
+    /// index of the item in the game's `items`, missing for the free space
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature="swagger-ui", schema(example = 12))]
+    idx: Option<usize>,
+    #[cfg_attr(feature="swagger-ui", schema(value_type = String, example = "streamer says \"chat\""))]
+    text: Arc<str>,
+    /// whether the player marked this cell
+    marked: bool,
+}
+
+/// a player's own bingo card
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToResponse, utoipa::ToSchema))]
+pub struct BoardData {
+    /// the seed this board was generated from, it's a string since it doesn't
+    /// fit in a javascript number
+    #[cfg_attr(feature="swagger-ui", schema(example = "9748205612937562514"))]
+    seed: String,
+    #[cfg_attr(feature="swagger-ui", schema(example = 5))]
+    size: u32,
+    /// the cells of the board, row by row
+    cells: Vec<Cell>,
+}
+
+impl BoardData {
+    pub fn new(game: &Game, player: &PlayerData) -> Self {
+        let items = game.get_items();
+        let free_space: Arc<str> = game.get_options().free_space_label().unwrap_or_default().into();
+        Self {
+            seed: player.seed().to_string(),
+            size: game.get_size(),
+            cells: player.board().iter().zip(player.marks()).map(|(idx, marked)| Cell {
+                idx: *idx,
+                text: match idx {
+                    Some(idx) => items[*idx].text.clone(),
+                    None => free_space.clone(),
+                },
+                marked: *marked,
+            }).collect(),
+        }
+    }
+}
+
+/// get the board of the logged in player
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    get,
+    path = "/game/{id}/board",
+    tag = "Game",
+    security(
+        ("user_token" = [])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 200, description = "The player's board", body = BoardData),
+        (status = 404, description = "No game with that ULID was found or the player didn't join it", body = GameError),
+        (status = 409, description = "The player is waiting in the lobby and has no board yet", body = GameError)
+    )
+))]
+pub async fn get_board(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<BoardData>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+
+    let player = game.get_player(claims.user_id())
+        .ok_or_else(|| game.missing_player(claims.user_id()))?;
+
+    return Ok(Json(BoardData::new(&game, &player)));
+}
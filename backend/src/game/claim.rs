@@ -0,0 +1,42 @@
+use actix_web::web::{Data, Json, Path};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager, win::Win};
+
+/// claim a bingo on the logged in player's board
+///
+/// every win on the board that wasn't claimed yet counts, and the round ends
+/// unless later wins can still score. If the board has no win at all, the
+/// player's websockets are told the claim was rejected, but only once between
+/// picks.
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/claim",
+    tag = "Game",
+    security(
+        ("user_token" = [])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 200, description = "The claim was correct, the player won with every win that wasn't claimed yet", body = [Win]),
+        (status = 400, description = "The player's board doesn't have a bingo", body = GameError),
+        (status = 404, description = "No game with that ULID was found or the player didn't join it", body = GameError),
+        (status = 409, description = "The game isn't running, the player has no board yet or already claimed every win on it", body = GameError)
+    )
+))]
+pub async fn claim_bingo(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<Vec<Win>>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+
+    let wins = game.claim_bingo(claims.user_id())?;
+    games_manager.save_game(&game);
+
+    return Ok(Json(wins));
+}
@@ -0,0 +1,69 @@
+use hashbrown::HashMap;
+use parking_lot::RwLock;
+use rand::Rng;
+use ulid::Ulid;
+
+/// the characters codes are made of, without the ones that are easy to mix up
+/// when read aloud or off a stream, like 0 and O or 1 and I
+pub const ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// how many characters join codes have
+pub const CODE_LENGTH: usize = 6;
+
+/// uppercases `code` and strips the spaces and dashes people put in it
+pub fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// short codes that stand in for the ULIDs of games, every code belongs to at
+/// most one game at a time
+#[derive(Debug, Default)]
+pub struct Codes {
+    codes: RwLock<HashMap<String, Ulid>>,
+}
+
+impl Codes {
+    /// generates a code of `len` characters that no other game has and gives
+    /// it to the game `id`
+    pub fn issue(&self, id: Ulid, len: usize) -> String {
+        let mut rng = rand::thread_rng();
+        let mut codes = self.codes.write();
+        loop {
+            let code: String = (0..len)
+                .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+                .collect();
+            if !codes.contains_key(&code) {
+                codes.insert(code.clone(), id);
+                return code;
+            }
+        }
+    }
+
+    /// gives `code` back to the game `id` after a restart
+    ///
+    /// returns false if another game already has it
+    pub fn restore(&self, code: &str, id: Ulid) -> bool {
+        let mut codes = self.codes.write();
+        match codes.get(code) {
+            Some(owner) => *owner == id,
+            None => {
+                codes.insert(code.to_owned(), id);
+                true
+            },
+        }
+    }
+
+    /// frees `code` so it can be issued again
+    pub fn release(&self, code: &str) {
+        self.codes.write().remove(code);
+    }
+
+    /// the ULID of the game `code` belongs to, in any case and with or without
+    /// spaces and dashes
+    pub fn resolve(&self, code: &str) -> Option<Ulid> {
+        self.codes.read().get(&normalize(code)).copied()
+    }
+}
@@ -1,16 +1,27 @@
-use actix_web::{web::{Data, Json}, HttpRequest, HttpResponseBuilder, ResponseError};
+use actix_web::{web::{Data, Json}, HttpResponseBuilder, ResponseError};
+use hashbrown::HashSet;
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
 
-use super::{manager::GamesManager, Game, Item};
+use crate::auth::jwt::Claims;
+
+use super::{access::Visibility, info::GameInfo, manager::GamesManager, options::GameOptions, Game, Item, MAX_ITEM_LENGTH};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
 pub struct CreateGameRequest {
-    // name: String,
+    #[serde(flatten)]
+    pub(super) info: GameInfo,
     pub(super) items: Vec<String>,
     #[cfg_attr(feature="swagger-ui", schema(minimum = 5, maximum = 23))]
     pub(super) size: u32,
+    #[serde(default)]
+    pub(super) options: GameOptions,
+    #[serde(default)]
+    pub(super) visibility: Visibility,
+    /// what players can join a private game with besides its invite code
+    #[cfg_attr(feature="swagger-ui", schema(example = "kappa123", max_length = 64))]
+    pub(super) password: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, thiserror::Error)]
@@ -24,14 +35,46 @@ pub enum CreateError {
     #[error("the board was too big!")]
     TooBig,
     #[error("the board was too small")]
-    TooSmall
+    TooSmall,
+    #[error("a win pattern didn't fit the size of the board")]
+    InvalidPattern,
+    #[error("items can't be picked automatically more than once a second")]
+    InvalidAutoPick,
+    #[error("polls have to stay open for at least a second and need at least one vote")]
+    InvalidVoting,
+    #[error("an item was too long")]
+    ItemTooLong,
+    #[error("an item was in the game more than once")]
+    DuplicateItem,
+    #[error("the title was too long")]
+    TitleTooLong,
+    #[error("the description was too long")]
+    DescriptionTooLong,
+    #[error("a text had control characters in it")]
+    InvalidText,
+    #[error("the cover wasn't a valid http(s) URL")]
+    InvalidCoverUrl,
+    #[error("the language wasn't a valid BCP 47 tag")]
+    InvalidLanguage,
+    #[error("there were more than 10 tags, or a tag was repeated, longer than 25 characters or had characters other than letters and numbers")]
+    InvalidTags,
+    #[error("the options weren't valid")]
+    InvalidOptions,
+    #[error("the password was longer than 64 characters or had control characters in it")]
+    InvalidPassword,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature="swagger-ui", derive(utoipa::ToResponse, utoipa::ToSchema))]
 pub struct CreatedGame {
     #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
-    pub(super) id: Ulid
+    pub(super) id: Ulid,
+    /// the short code players can join with instead of the ULID
+    #[cfg_attr(feature="swagger-ui", schema(example = "K7PM2X"))]
+    pub(super) code: Option<String>,
+    /// the code to share for joining the game if it's private
+    #[cfg_attr(feature="swagger-ui", schema(example = "K7PM2XQR"))]
+    pub(super) invite: Option<String>,
 }
 
 impl ResponseError for CreateError {
@@ -55,27 +98,72 @@ impl ResponseError for CreateError {
     request_body(
         content = CreateGameRequest,
         description = "The information of a basic bingo name, `size` must be an
-        odd number between 5 and 23 inclusive, and `items` must be of minimum length `size`^2"
+        odd number between 5 and 23 inclusive, and `items` must be of minimum length `size`^2,
+        or `size`^2 - 1 if the board has a free space, with no item longer than 100 characters
+        or repeated, ignoring case and whitespace. The title, description, cover, language,
+        tags and password are all optional"
     ),
     responses(
         (status = 200, description = "A game was created succesfully", body = CreatedGame),
-        (status = 400, description = "The request to create a game was invalid", body = CreateError)
+        (status = 400, description = "The request to create a game was invalid", body = CreateError),
+        (status = 401, description = "The request wasn't authenticated")
     )
 ))]
-pub async fn create_game(req: HttpRequest, game: Json<CreateGameRequest>, games_manager: Data<GamesManager>) -> Result<Json<CreatedGame>, CreateError> {
-    match game.size {
+pub async fn create_game(
+    claims: Claims,
+    game: Json<CreateGameRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<CreatedGame>, CreateError> {
+    let items: Vec<&str> = game.items.iter().map(String::as_str).collect();
+    validate(game.size, &items, &game.options)?;
+    let info = game.info.clone().trimmed();
+    info.validate()?;
+
+    let ulid = Ulid::new();
+
+    let game = game.into_inner();
+    let items: Box<[Item]> = game.items.into_iter().map(|i| i.into()).collect();
+
+    let mut options = game.options;
+    if options.patterns.is_empty() {
+        options.patterns = GameOptions::default().patterns;
+    }
+
+    let new_game = Game::new(ulid, game.size, items, options)
+        .with_creator(claims.user_id())
+        .with_info(info);
+    new_game.set_access(Some(game.visibility), game.password)?;
+    let game = games_manager.new_game(new_game);
+
+    return Ok(Json(CreatedGame { id: ulid, code: game.get_code(), invite: game.get_access().invite }));
+}
+
+/// checks that a game with these settings can be played, for both new games
+/// and edits to games in the lobby
+pub(super) fn validate(size: u32, items: &[&str], options: &GameOptions) -> Result<(), CreateError> {
+    match size {
         x if x > 23  => return Err(CreateError::TooBig),
         x if x < 5 => return Err(CreateError::TooSmall),
         x if x % 2 != 1 => return Err(CreateError::SizeNotOdd),
-        x if game.items.len() < x.pow(2) as usize => return Err(CreateError::NotEnoughItems),
+        x if items.len() < options.items_needed(x) => return Err(CreateError::NotEnoughItems),
         _ => ()
     };
 
-    let ulid = Ulid::new();
-
-    let items: Box<[Item]> = game.0.items.into_iter().map(|i| i.into()).collect();
-
-    games_manager.new_game(Game::new(ulid, game.0.size, items));
-
-    return Ok(Json(CreatedGame { id: ulid }));
+    if !options.patterns.iter().all(|p| p.fits(size)) {
+        return Err(CreateError::InvalidPattern);
+    }
+    if options.auto_pick_interval == Some(0) {
+        return Err(CreateError::InvalidAutoPick);
+    }
+    if options.voting.window == 0 || options.voting.threshold == Some(0) {
+        return Err(CreateError::InvalidVoting);
+    }
+    if items.iter().any(|i| i.chars().count() > MAX_ITEM_LENGTH) {
+        return Err(CreateError::ItemTooLong);
+    }
+    let mut seen = HashSet::new();
+    if !items.iter().all(|i| seen.insert(Item::normalize(i))) {
+        return Err(CreateError::DuplicateItem);
+    }
+    Ok(())
 }
@@ -0,0 +1,101 @@
+use actix_web::{HttpResponseBuilder, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use super::lifecycle::GameState;
+
+/// errors that can happen when acting on an ongoing game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[serde(rename_all="snake_case", tag="error")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum GameError {
+    #[error("there is no such game")]
+    NoSuchGame,
+    #[error("this action requires being logged in")]
+    Unauthenticated,
+    #[error("your role in this game doesn't allow that")]
+    Forbidden,
+    #[error("you haven't joined this game")]
+    NotInGame,
+    #[error("boards are only dealt once the game starts")]
+    NotDealtYet,
+    #[error("the game isn't running")]
+    NotRunning,
+    #[error("boards were already dealt, the game can't be edited anymore")]
+    AlreadyDealt,
+    #[error("a game can't go from {from:?} to {to:?}")]
+    InvalidTransition {
+        from: GameState,
+        to: GameState,
+    },
+    #[error("there is no item with that index")]
+    NoSuchItem,
+    #[error("there is no cell with that index on your board")]
+    NoSuchCell,
+    #[error("that item hasn't been picked")]
+    NotPicked,
+    #[error("that item was already picked")]
+    AlreadyPicked,
+    #[error("the game is already over")]
+    GameOver,
+    #[error("your board doesn't have a bingo")]
+    NoBingo,
+    #[error("you already claimed every win on your board")]
+    AlreadyWon,
+    #[error("suggestions are only taken while the game is in the lobby")]
+    SuggestionsClosed,
+    #[error("you can't suggest any more items")]
+    TooManySuggestions,
+    #[error("there is no pending suggestion with that ID")]
+    NoSuchSuggestion,
+    #[error("items can't be empty")]
+    EmptyItem,
+    #[error("the item is too long")]
+    ItemTooLong,
+    #[error("that item is already in the game")]
+    DuplicateItem,
+    #[error("there is no open poll with that ID")]
+    NoSuchPoll,
+    #[error("another poll is still open")]
+    PollOpen,
+    #[error("you already voted in this poll")]
+    AlreadyVoted,
+    #[error("no user with that login has logged in before")]
+    NoSuchUser,
+    #[error("the owner of a game can't be given or lose that role")]
+    InvalidRole,
+    #[error("this game is private, joining it takes its invite code or password")]
+    Private,
+    #[error("no game has that invite code")]
+    NoSuchInvite,
+    #[error("this game has as many spectators as it can take")]
+    TooManySpectators,
+}
+
+impl ResponseError for GameError {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        match self {
+            GameError::NoSuchGame | GameError::NotInGame | GameError::NoSuchPoll | GameError::NoSuchSuggestion | GameError::NoSuchUser | GameError::NoSuchInvite => actix_web::http::StatusCode::NOT_FOUND,
+            GameError::Unauthenticated => actix_web::http::StatusCode::UNAUTHORIZED,
+            GameError::Forbidden | GameError::Private => actix_web::http::StatusCode::FORBIDDEN,
+            GameError::AlreadyPicked
+            | GameError::GameOver
+            | GameError::NotDealtYet
+            | GameError::NotRunning
+            | GameError::AlreadyDealt
+            | GameError::NotPicked
+            | GameError::PollOpen
+            | GameError::SuggestionsClosed
+            | GameError::TooManySuggestions
+            | GameError::DuplicateItem
+            | GameError::AlreadyVoted
+            | GameError::AlreadyWon
+            | GameError::TooManySpectators
+            | GameError::InvalidTransition { .. } => actix_web::http::StatusCode::CONFLICT,
+            _ => actix_web::http::StatusCode::BAD_REQUEST
+        }
+    }
+
+    fn error_response(&self) -> actix_web::HttpResponse<actix_web::body::BoxBody> {
+        HttpResponseBuilder::new(self.status_code()).json(self)
+    }
+}
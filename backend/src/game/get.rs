@@ -1,24 +1,58 @@
-use actix_web::{web::{Data, Query}, HttpRequest, HttpResponse, Responder};
+use actix_web::{web::{Data, Query}, HttpResponse, Responder, ResponseError};
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
 
-use super::{manager::GamesManager, Item};
+use crate::auth::jwt::Claims;
+
+use super::{access::{AccessKey, Visibility}, info::GameInfo, lifecycle::GameState, manager::GamesManager, options::WinMode, round::Round, win::{WinPattern, Winner}, Item};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature="swagger-ui", derive(utoipa::IntoParams, utoipa::ToSchema))]
 pub struct GameRequest {
-    /// the ULID of the game
+    /// the ULID of the game or its join code
     #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
-    pub(super) id: Ulid
+    pub(super) id: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
 #[cfg_attr(feature="swagger-ui", derive(utoipa::ToResponse, utoipa::ToSchema))]
 pub struct GameData<'a> {
+    #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
+    id: Ulid,
+    /// the short code players can join with instead of the ULID
+    #[cfg_attr(feature="swagger-ui", schema(example = "K7PM2X"))]
+    code: Option<String>,
+    #[serde(flatten)]
+    info: GameInfo,
+    visibility: Visibility,
+    /// how many players joined, including the ones waiting in the lobby
+    #[cfg_attr(feature="swagger-ui", schema(example = 42))]
+    players: usize,
+    /// how many spectators are watching, they don't have boards
+    #[cfg_attr(feature="swagger-ui", schema(example = 3))]
+    spectators: usize,
+    state: GameState,
+    /// the number of the current round, starting at 1
+    #[cfg_attr(feature="swagger-ui", schema(example = 2))]
+    round: u32,
     #[cfg_attr(feature="swagger-ui", schema(inline))]
     items: &'a[Item],
+    /// indices of the picked items, in the order they were picked
+    #[cfg_attr(feature="swagger-ui", schema(example = json!([4, 0, 12])))]
+    picks: &'a[usize],
     #[cfg_attr(feature="swagger-ui", schema(example = 7))]
-    size: u32
+    size: u32,
+    /// the patterns that win the game
+    patterns: &'a [WinPattern],
+    /// the text on the center cell of every board, if it is a free space
+    #[cfg_attr(feature="swagger-ui", schema(example = "FREE"))]
+    free_space: Option<&'a str>,
+    /// whether players have to claim their wins
+    win_mode: WinMode,
+    /// everyone who won the current round so far, in the order they won
+    winners: &'a [Winner],
+    /// the rounds played before the current one
+    rounds: &'a [Round],
 }
 
 /// get the current information of an ongoing game
@@ -26,19 +60,43 @@ pub struct GameData<'a> {
     get,
     path="/game/get",
     tag = "Game",
-    params(GameRequest),
+    params(GameRequest, AccessKey),
     responses(
-        (status = 400, description = "no game with that ULID was found"),
+        (status = 400, description = "no game with that ULID or join code was found"),
+        (status = 403, description = "the game is private and the key was missing or wrong", body = super::error::GameError),
         (status = 200, description = "the game data the client needs to start playing", body = GameData)
     )
 ))]
-pub async fn get_game(req: HttpRequest, query: Query<GameRequest>) -> impl Responder {
-    let game = req.app_data::<Data<GamesManager>>().unwrap().get_game(query.id);
+pub async fn get_game(query: Query<GameRequest>, key: AccessKey, claims: Option<Claims>, games_manager: Data<GamesManager>) -> impl Responder {
+    let game = games_manager.find_game(&query.id);
 
     if let Some(game) = game {
+        if let Err(e) = game.check_access(claims.as_ref(), key.0.as_deref()) {
+            return e.error_response();
+        }
+
+        let items = game.get_items();
+        let picks = game.get_picks();
+        let winners = game.get_winners();
+        let rounds = game.get_rounds();
+        let options = game.get_options();
         let data = GameData {
-            items: game.get_items(),
-            size: game.get_size()
+            id: game.get_id(),
+            code: game.get_code(),
+            info: game.get_info(),
+            visibility: game.get_access().visibility,
+            players: game.player_count(),
+            spectators: game.spectator_count(),
+            state: game.get_state(),
+            round: game.get_round(),
+            items: &items,
+            picks: &picks,
+            size: game.get_size(),
+            patterns: &options.patterns,
+            free_space: options.free_space_label(),
+            win_mode: options.win_mode,
+            winners: &winners,
+            rounds: &rounds,
         };
 
         HttpResponse::Ok().json(data)
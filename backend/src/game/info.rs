@@ -0,0 +1,97 @@
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::create::CreateError;
+
+pub const MAX_TITLE_LENGTH: usize = 100;
+pub const MAX_DESCRIPTION_LENGTH: usize = 1000;
+pub const MAX_URL_LENGTH: usize = 500;
+pub const MAX_TAGS: usize = 10;
+/// the same as for twitch's stream tags
+pub const MAX_TAG_LENGTH: usize = 25;
+
+/// what a game is about, for listing games on a streamer's page and in
+/// overlays
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct GameInfo {
+    /// at most 100 characters
+    #[cfg_attr(feature="swagger-ui", schema(example = "Just Chatting bingo"))]
+    pub title: Option<String>,
+    /// at most 1000 characters
+    #[cfg_attr(feature="swagger-ui", schema(example = "Everything that happens on stream tonight"))]
+    pub description: Option<String>,
+    /// an http(s) link to an emote or image shown along with the game
+    #[cfg_attr(feature="swagger-ui", schema(example = "https://static-cdn.jtvnw.net/emoticons/v2/25/default/dark/3.0"))]
+    pub cover_url: Option<String>,
+    /// the language the game is played in, as a BCP 47 tag
+    #[cfg_attr(feature="swagger-ui", schema(example = "pt-BR"))]
+    pub language: Option<String>,
+    /// at most 10 tags of letters and numbers, like twitch's stream tags
+    #[cfg_attr(feature="swagger-ui", schema(example = json!(["English", "JustChatting"])))]
+    pub tags: Vec<String>,
+}
+
+impl GameInfo {
+    /// trims every text, leaving out the ones that are empty after that
+    pub fn trimmed(self) -> Self {
+        let trim = |text: Option<String>| text
+            .map(|t| t.trim().to_owned())
+            .filter(|t| !t.is_empty());
+        Self {
+            title: trim(self.title),
+            description: trim(self.description),
+            cover_url: trim(self.cover_url),
+            language: trim(self.language),
+            tags: self.tags.into_iter().map(|t| t.trim().to_owned()).collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), CreateError> {
+        // descriptions can span multiple lines, nothing else can
+        let texts = self.title.iter().chain(&self.cover_url).chain(&self.language).chain(&self.tags);
+        if texts.flat_map(|t| t.chars()).any(char::is_control)
+            || self.description.iter().flat_map(|d| d.chars()).any(|c| c.is_control() && c != '\n') {
+            return Err(CreateError::InvalidText);
+        }
+        if self.title.as_ref().is_some_and(|t| t.chars().count() > MAX_TITLE_LENGTH) {
+            return Err(CreateError::TitleTooLong);
+        }
+        if self.description.as_ref().is_some_and(|d| d.chars().count() > MAX_DESCRIPTION_LENGTH) {
+            return Err(CreateError::DescriptionTooLong);
+        }
+        if self.cover_url.as_deref().is_some_and(|u| !is_valid_url(u)) {
+            return Err(CreateError::InvalidCoverUrl);
+        }
+        if self.language.as_deref().is_some_and(|l| !is_valid_language(l)) {
+            return Err(CreateError::InvalidLanguage);
+        }
+        let mut seen = HashSet::new();
+        let tags_valid = self.tags.len() <= MAX_TAGS && self.tags.iter().all(|t| {
+            (1..=MAX_TAG_LENGTH).contains(&t.chars().count())
+                && t.chars().all(char::is_alphanumeric)
+                && seen.insert(t.to_lowercase())
+        });
+        if !tags_valid {
+            return Err(CreateError::InvalidTags);
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_url(url: &str) -> bool {
+    url.len() <= MAX_URL_LENGTH
+        && Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.has_host())
+}
+
+/// whether `language` looks like a BCP 47 tag, a 2 or 3 letter language
+/// followed by subtags of up to 8 letters and numbers
+fn is_valid_language(language: &str) -> bool {
+    let mut subtags = language.split('-');
+    let primary = subtags.next().unwrap_or_default();
+    (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|s| (1..=8).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric()))
+}
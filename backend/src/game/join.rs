@@ -0,0 +1,48 @@
+use actix_web::{web::{Data, Path}, HttpResponse};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{access::AccessKey, board::BoardData, error::GameError, manager::GamesManager};
+
+/// join a game, dealing a board to the logged in player
+///
+/// players joining while the game is in the lobby get their board once it
+/// starts, joining again, even from another device, returns the same board
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/join",
+    tag = "Game",
+    security(
+        ("user_token" = [])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game"),
+        AccessKey
+    ),
+    responses(
+        (status = 200, description = "The player's board", body = BoardData),
+        (status = 202, description = "The player is waiting in the lobby for the game to start"),
+        (status = 403, description = "The game is private and the key was missing or wrong, or the user is a spectator", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The game is already over", body = GameError)
+    )
+))]
+pub async fn join_game(
+    claims: Claims,
+    id: Path<Ulid>,
+    key: AccessKey,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.check_access(Some(&claims), key.0.as_deref())?;
+
+    if game.add_new_player(claims.user_id())? {
+        games_manager.save_player(&game, claims.user_id());
+    }
+
+    return match game.get_player(claims.user_id()) {
+        Some(player) => Ok(HttpResponse::Ok().json(BoardData::new(&game, &player))),
+        None => Ok(HttpResponse::Accepted().finish()),
+    };
+}
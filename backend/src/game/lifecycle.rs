@@ -0,0 +1,201 @@
+use std::str::FromStr;
+
+use actix_web::{web::{Data, Path}, HttpResponse};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager, role::Permission};
+
+/// where a game is in its lifecycle
+///
+/// games start in the lobby, where players can join but nothing is picked,
+/// and go through running and paused until they're finished, after which they
+/// only get archived, or run again for a new round or if their wins are
+/// revoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum GameState {
+    Lobby,
+    Running,
+    Paused,
+    Finished,
+    Archived,
+}
+
+impl GameState {
+    pub fn can_transition_to(self, to: GameState) -> bool {
+        use GameState::*;
+        matches!(
+            (self, to),
+            (Lobby, Running)
+            | (Running, Paused)
+            | (Paused, Running)
+            | (Lobby | Running | Paused, Finished)
+            | (Finished, Archived)
+            // when every win that finished the game gets revoked, or a new
+            // round starts
+            | (Finished, Running)
+        )
+    }
+
+    /// whether nothing can happen in the game anymore
+    pub fn is_over(self) -> bool {
+        matches!(self, GameState::Finished | GameState::Archived)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GameState::Lobby => "lobby",
+            GameState::Running => "running",
+            GameState::Paused => "paused",
+            GameState::Finished => "finished",
+            GameState::Archived => "archived",
+        }
+    }
+}
+
+impl FromStr for GameState {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lobby" => Ok(GameState::Lobby),
+            "running" => Ok(GameState::Running),
+            "paused" => Ok(GameState::Paused),
+            "finished" => Ok(GameState::Finished),
+            "archived" => Ok(GameState::Archived),
+            _ => Err(()),
+        }
+    }
+}
+
+/// start a game that is still in the lobby, dealing everyone's boards
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/start",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 204, description = "The game started"),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The game wasn't in the lobby", body = GameError)
+    )
+))]
+pub async fn start_game(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    let dealt = game.start()?;
+    games_manager.save_players(&game, dealt);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
+
+/// pause a running game, nothing can be picked until it is resumed
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/pause",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 204, description = "The game was paused"),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The game wasn't running", body = GameError)
+    )
+))]
+pub async fn pause_game(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    game.pause()?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
+
+/// resume a paused game
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/resume",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 204, description = "The game is running again"),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The game wasn't paused", body = GameError)
+    )
+))]
+pub async fn resume_game(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    game.resume()?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
+
+/// end a game early, without a winner
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/end",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 204, description = "The game is finished"),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The game was already over", body = GameError)
+    )
+))]
+pub async fn end_game(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    game.end()?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
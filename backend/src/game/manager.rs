@@ -1,14 +1,73 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
+use actix_web::web::Data;
 use hashbrown::HashMap;
-use parking_lot::RwLock;
+use log::{error, info};
+use parking_lot::{Mutex, RwLock};
+use tokio::task::JoinHandle;
 use ulid::Ulid;
 
-use super::Game;
+use crate::{event::ServerEvent, metrics::GAMES_EVICTED};
+
+use super::{access::INVITE_LENGTH, auto_pick, code::{Codes, CODE_LENGTH}, spectator::Spectator, error::GameError, lifecycle::GameState, store::GameStore, vote::{self, OpenedPoll}, Game};
+
+/// why the sweeper removed a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    /// nothing happened in the game for too long and nobody but spectators
+    /// was connected
+    Idle,
+    /// the game was around for longer than it is allowed to
+    Expired,
+}
+
+impl Eviction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Eviction::Idle => "idle",
+            Eviction::Expired => "expired",
+        }
+    }
+}
+
+/// when games get removed by the sweeper
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expiry {
+    /// how long a game can go without activity and without any players or
+    /// hosts connected, spectators alone don't keep a game around
+    pub idle_timeout: Duration,
+    /// how long a game can exist at all
+    pub max_lifetime: Duration,
+}
+
+impl Expiry {
+    fn check(&self, game: &Game) -> Option<Eviction> {
+        if game.age() >= self.max_lifetime {
+            Some(Eviction::Expired)
+        } else if game.connection_count().saturating_sub(game.spectator_count()) == 0 && game.idle_time() >= self.idle_timeout {
+            Some(Eviction::Idle)
+        } else {
+            None
+        }
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct GamesManager {
-    games: RwLock<HashMap<Ulid, Arc<Game>>>
+    games: RwLock<HashMap<Ulid, Arc<Game>>>,
+    /// where games are saved to, they only live in memory if there's none
+    store: Option<GameStore>,
+    /// the tasks picking items in games with [auto_pick_interval] set
+    ///
+    /// [auto_pick_interval]: super::options::GameOptions::auto_pick_interval
+    auto_pickers: Mutex<HashMap<Ulid, JoinHandle<()>>>,
+    /// the join codes of every game
+    codes: Codes,
+    /// the invite codes of every game
+    invites: Codes,
+    /// how many spectators can watch a single game, there's no limit if
+    /// there's none
+    max_spectators: Option<usize>,
 }
 
 impl GamesManager {
@@ -18,15 +77,256 @@ impl GamesManager {
         }
     }
 
-    pub fn new_game(&self, game: Game) {
-        self.games.write().insert(game.id, Arc::new(game));
+    pub fn with_store(store: GameStore) -> Self {
+        Self {
+            store: Some(store),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_spectators(mut self, max: usize) -> Self {
+        self.max_spectators = Some(max);
+        self
+    }
+
+    /// loads every game that was still active from the store
+    ///
+    /// returns how many games were loaded
+    pub async fn load_games(&self) -> Result<usize, sqlx::Error> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        let games = store.load_active().await?;
+        let count = games.len();
+        let mut map = self.games.write();
+        for game in games {
+            let game = Arc::new(game);
+            game.set_code(self.codes.issue(game.id, CODE_LENGTH));
+            let restored = game.get_access().invite.is_some_and(|code| self.invites.restore(&code, game.id));
+            if !restored {
+                // games from before invite codes, or whose code was taken
+                game.set_invite(self.invites.issue(game.id, INVITE_LENGTH));
+                self.save_game(&game);
+            }
+            self.start_auto_pick(&game);
+            map.insert(game.id, game);
+        }
+        Ok(count)
+    }
+
+    pub fn new_game(&self, game: Game) -> Arc<Game> {
+        let game = Arc::new(game);
+        game.set_code(self.codes.issue(game.id, CODE_LENGTH));
+        game.set_invite(self.invites.issue(game.id, INVITE_LENGTH));
+        self.games.write().insert(game.id, Arc::clone(&game));
+        self.start_auto_pick(&game);
+        self.save_game(&game);
+        game
     }
 
     pub fn remove_game(&self, id: Ulid) {
-        self.games.write().remove(&id);
+        let removed = self.games.write().remove(&id);
+        if let Some(game) = removed {
+            self.forget_game(game);
+        }
     }
 
     pub fn get_game(&self, id: Ulid) -> Option<Arc<Game>> {
-        self.games.read().get(&id).map(|a| Arc::clone(a))
+        self.games.read().get(&id).map(Arc::clone)
+    }
+
+    /// the game `id` refers to, either by its ULID or by its join code
+    pub fn find_game(&self, id: &str) -> Option<Arc<Game>> {
+        match id.parse() {
+            Ok(id) => self.get_game(id),
+            Err(_) => self.get_game(self.codes.resolve(id)?),
+        }
+    }
+
+    /// removes every game that is past `expiry`, ending and archiving them and
+    /// telling anyone still connected that they're over
+    ///
+    /// returns the removed games and why they were removed
+    pub fn sweep(&self, expiry: &Expiry) -> Vec<(Ulid, Eviction)> {
+        let mut evicted = Vec::new();
+        self.games.write().retain(|id, game| match expiry.check(game) {
+            Some(reason) => {
+                evicted.push((*id, Arc::clone(game), reason));
+                false
+            },
+            None => true,
+        });
+
+        evicted.into_iter().map(|(id, game, reason)| {
+            if game.get_state() != GameState::Archived {
+                if game.is_over() {
+                    game.send_event(ServerEvent::GameOver);
+                } else {
+                    // this already tells everyone the game is over
+                    let _ = game.end();
+                }
+                let _ = game.archive();
+            }
+            GAMES_EVICTED.with_label_values(&[reason.as_str()]).inc();
+            info!("removed game {id} ({})", reason.as_str());
+            self.forget_game(game);
+            (id, reason)
+        }).collect()
+    }
+
+    /// cleans up after `game` was taken out of the running games, freeing its
+    /// codes and its auto picker and saving it one last time as inactive
+    fn forget_game(&self, game: Arc<Game>) {
+        let id = game.id;
+        self.stop_auto_pick(id);
+        if let Some(code) = game.get_code() {
+            self.codes.release(&code);
+        }
+        if let Some(code) = game.get_access().invite {
+            self.invites.release(&code);
+        }
+
+        if let Some(store) = self.store.clone() {
+            tokio::spawn(async move {
+                if let Err(e) = store.save_game(&game).await {
+                    error!("failed to save removed game {id}: {e}");
+                }
+                if let Err(e) = store.deactivate(id).await {
+                    error!("failed to deactivate game {id}: {e}");
+                }
+            });
+        }
+    }
+
+    /// gives `game` a new invite code, the old one stops working right away
+    pub fn rotate_invite(&self, game: &Arc<Game>) -> String {
+        let code = self.invites.issue(game.id, INVITE_LENGTH);
+        if let Some(old) = game.set_invite(code.clone()) {
+            self.invites.release(&old);
+        }
+        self.save_game(game);
+        code
+    }
+
+    /// takes a spot among the spectators of `game`, if it doesn't have as many
+    /// as it can take already
+    pub fn spectate(&self, game: &Arc<Game>) -> Result<Spectator, GameError> {
+        game.spectate(self.max_spectators)
+    }
+
+    /// the ULID of the game the invite code `code` belongs to
+    pub fn resolve_invite(&self, code: &str) -> Option<Ulid> {
+        self.invites.resolve(code)
+    }
+
+    /// spawns the task picking items in `game` if it has an auto pick interval
+    fn start_auto_pick(&self, game: &Arc<Game>) {
+        if let Some(interval) = game.get_options().auto_pick_interval {
+            let handle = tokio::spawn(auto_pick::run(
+                Arc::clone(game),
+                Duration::from_secs(interval.into()),
+                self.store.clone()
+            ));
+            self.auto_pickers.lock().insert(game.id, handle);
+        }
+    }
+
+    fn stop_auto_pick(&self, id: Ulid) {
+        if let Some(handle) = self.auto_pickers.lock().remove(&id) {
+            handle.abort();
+        }
+    }
+
+    /// restarts the task picking items in `game`, after its auto pick
+    /// interval might have changed
+    pub fn restart_auto_pick(&self, game: &Arc<Game>) {
+        self.stop_auto_pick(game.id);
+        self.start_auto_pick(game);
+    }
+
+    /// opens a poll on the item at `idx` in `game`, closing it again once its
+    /// window is over
+    pub fn open_poll(&self, game: &Arc<Game>, idx: usize) -> Result<OpenedPoll, GameError> {
+        let opened = game.open_poll(idx)?;
+        tokio::spawn(vote::expire(Arc::downgrade(game), opened.poll, game.get_options().voting.window));
+        Ok(opened)
+    }
+
+    /// spawns a task that calls [GamesManager::sweep] every `interval`
+    pub fn spawn_sweeper(manager: Data<GamesManager>, expiry: Expiry, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                manager.sweep(&expiry);
+            }
+        })
+    }
+
+    /// the twitch display names of the users with the ULIDs `ids`, empty if
+    /// games aren't stored anywhere or the names couldn't be loaded
+    pub async fn display_names(&self, ids: &[Ulid]) -> HashMap<Ulid, String> {
+        let Some(store) = &self.store else {
+            return HashMap::new();
+        };
+        match store.display_names(ids).await {
+            Ok(names) => names,
+            Err(e) => {
+                error!("failed to load display names: {e}");
+                HashMap::new()
+            },
+        }
+    }
+
+    /// the ULID of the user with the twitch login `login`, games without a
+    /// database don't know anyone
+    pub async fn find_user(&self, login: &str) -> Option<Ulid> {
+        let store = self.store.as_ref()?;
+        match store.find_user(login).await {
+            Ok(id) => id,
+            Err(e) => {
+                error!("failed to look up user {login}: {e}");
+                None
+            },
+        }
+    }
+
+    /// saves the current state of `game` in the background
+    pub fn save_game(&self, game: &Arc<Game>) {
+        if let Some(store) = self.store.clone() {
+            let game = Arc::clone(game);
+            tokio::spawn(async move {
+                if let Err(e) = store.save_game(&game).await {
+                    error!("failed to save game {}: {e}", game.id);
+                }
+            });
+        }
+    }
+
+    /// saves the board of the player with the user ID `user_id` in the
+    /// background
+    pub fn save_player(&self, game: &Arc<Game>, user_id: Ulid) {
+        if let Some(store) = self.store.clone() {
+            let game = Arc::clone(game);
+            tokio::spawn(async move {
+                if let Err(e) = store.save_player(&game, user_id).await {
+                    error!("failed to save player {user_id} of game {}: {e}", game.id);
+                }
+            });
+        }
+    }
+
+    /// saves the boards of the players with the user IDs `user_ids` along with
+    /// the game in the background
+    pub fn save_players(&self, game: &Arc<Game>, user_ids: Vec<Ulid>) {
+        if let Some(store) = self.store.clone() {
+            let game = Arc::clone(game);
+            tokio::spawn(async move {
+                if let Err(e) = store.save_players(&game, &user_ids).await {
+                    error!("failed to save players of game {}: {e}", game.id);
+                }
+            });
+        }
     }
 }
@@ -0,0 +1,50 @@
+use actix_web::{web::{Data, Json, Path}, HttpResponse};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct MarkRequest {
+    /// index of the cell on the player's board, row by row
+    #[cfg_attr(feature="swagger-ui", schema(example = 7))]
+    pub(super) cell: usize,
+    /// whether the cell should be marked or unmarked
+    pub(super) marked: bool,
+}
+
+/// mark or unmark a cell on the logged in player's board
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/mark",
+    tag = "Game",
+    security(
+        ("user_token" = [])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = MarkRequest,
+    responses(
+        (status = 204, description = "The cell was marked or unmarked"),
+        (status = 400, description = "There is no cell with that index", body = GameError),
+        (status = 404, description = "No game with that ULID was found or the player didn't join it", body = GameError),
+        (status = 409, description = "The game is over, the player has no board yet or the item wasn't picked in a strict game", body = GameError)
+    )
+))]
+pub async fn mark_cell(
+    claims: Claims,
+    id: Path<Ulid>,
+    mark: Json<MarkRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+
+    game.mark_cell(claims.user_id(), mark.cell, mark.marked)?;
+    games_manager.save_player(&game, claims.user_id());
+
+    return Ok(HttpResponse::NoContent().finish());
+}
@@ -1,21 +1,42 @@
-use std::sync::Arc;
+use std::{sync::{atomic::{AtomicU32, AtomicUsize, Ordering}, Arc}, time::{Duration, Instant, SystemTime}};
 
 use actix_web::web;
-use hashbrown::HashMap;
-use parking_lot::RwLock;
+use hashbrown::{HashMap, HashSet};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast::{Receiver, Sender};
 use ulid::Ulid;
 
-use crate::event::ServerEvent;
+use crate::{auth::jwt::{Claims, UserKind}, event::{GameEvent, ServerEvent}};
 
-use self::playerdata::PlayerData;
+use self::{access::{Access, Visibility}, create::CreateError, error::GameError, info::GameInfo, lifecycle::GameState, options::{GameOptions, WinMode}, playerdata::PlayerData, role::{Permission, Role}, round::Round, score::ScoreChange, spectator::Spectator, suggest::{Suggestion, SuggestionStatus}, update::{UpdateError, UpdateGameRequest}, vote::{OpenedPoll, Poll}, win::{RevokeReason, Win, Winner}};
 
 pub mod manager;
+pub mod access;
+pub mod code;
+pub mod auto_pick;
 pub mod playerdata;
+pub mod error;
+pub mod options;
 pub mod create;
 pub mod get;
+pub mod info;
+pub mod join;
+pub mod board;
+pub mod lifecycle;
+pub mod role;
+pub mod round;
+pub mod score;
+pub mod spectator;
+pub mod pick;
+pub mod claim;
+pub mod mark;
+pub mod suggest;
 pub mod update;
+pub mod vote;
+pub mod win;
+pub mod store;
 
 #[cfg(test)]
 mod test;
@@ -23,49 +44,1026 @@ mod test;
 #[derive(Debug)]
 pub struct Game {
     id: Ulid,
-    size: u32,
-    items: Box<[Item]>,
+    /// the user ID of whoever created the game, games saved before creators
+    /// were recorded don't have one and can only be run by admins
+    creator: Option<Ulid>,
+    /// roles granted by the owner, everyone who isn't in here is a player
+    roles: RwLock<HashMap<Ulid, Role>>,
+    access: RwLock<Access>,
+    /// the short code players can type instead of the ULID, issued by the
+    /// [GamesManager](manager::GamesManager) whenever it takes the game, so
+    /// it's never saved and changes across restarts
+    code: RwLock<Option<String>>,
+    info: RwLock<GameInfo>,
+    size: AtomicU32,
+    items: RwLock<Box<[Item]>>,
+    /// only changed while the game is in the lobby, so it's shared to avoid
+    /// holding a lock while using it
+    options: RwLock<Arc<GameOptions>>,
+    state: RwLock<GameState>,
+    /// indices of the picked items, in the order they were picked
+    picks: RwLock<Vec<usize>>,
+    /// players who joined while in the lobby and are waiting for a board
+    lobby: RwLock<Vec<Ulid>>,
     players: RwLock<HashMap<Ulid, PlayerData>>,
-    event_sender: Sender<ServerEvent>,
+    /// everyone who won the current round so far, in the order they won
+    winners: RwLock<Vec<Winner>>,
+    /// the number of the current round, starting at 1
+    round: AtomicU32,
+    /// the rounds that were played before the current one
+    rounds: RwLock<Vec<Round>>,
+    /// every item players suggested, reviewed or not
+    suggestions: RwLock<Vec<Suggestion>>,
+    /// the vote on the next pick that is currently open, polls aren't saved
+    poll: Mutex<Option<Poll>>,
+    /// players whose claims were rejected since the last pick, each of them
+    /// only hears about one of them per pick
+    rejected_claims: Mutex<HashSet<Ulid>>,
+    event_sender: Sender<GameEvent>,
+    /// how many websockets are watching the game as spectators
+    spectators: AtomicUsize,
+    /// the last time anything happened in the game
+    last_activity: Mutex<Instant>,
+    /// held while saving the game to the database
+    save_lock: tokio::sync::Mutex<()>,
 }
 
 impl Game {
-    pub fn new(id: Ulid, size: u32, items: Box<[Item]>) -> Self {
+    pub fn new(id: Ulid, size: u32, items: Box<[Item]>, options: GameOptions) -> Self {
         assert!(
-            items.len() >= (size * size) as usize,
+            items.len() >= options.items_needed(size),
             "there must be at least {} items in a board of size {}, but there were only {}",
-            size.pow(2),
+            options.items_needed(size),
             size,
             items.len()
         );
-        let (tx, _rx) = tokio::sync::broadcast::channel(8);
+        let (tx, _rx) = tokio::sync::broadcast::channel(64);
         Self {
             id,
-            size,
-            items,
+            creator: None,
+            roles: Default::default(),
+            access: Default::default(),
+            code: Default::default(),
+            info: Default::default(),
+            size: AtomicU32::new(size),
+            items: RwLock::new(items),
+            options: RwLock::new(Arc::new(options)),
+            state: RwLock::new(GameState::Lobby),
+            picks: Default::default(),
+            lobby: Default::default(),
             players: Default::default(),
-            event_sender: tx
+            winners: Default::default(),
+            round: AtomicU32::new(1),
+            rounds: Default::default(),
+            suggestions: Default::default(),
+            poll: Default::default(),
+            rejected_claims: Default::default(),
+            event_sender: tx,
+            spectators: AtomicUsize::new(0),
+            last_activity: Mutex::new(Instant::now()),
+            save_lock: Default::default(),
+        }
+    }
+
+    /// rebuilds a game that was saved to the database
+    #[allow(clippy::too_many_arguments)]
+    pub fn restore(
+        id: Ulid,
+        creator: Option<Ulid>,
+        roles: HashMap<Ulid, Role>,
+        access: Access,
+        info: GameInfo,
+        size: u32,
+        items: Box<[Item]>,
+        options: GameOptions,
+        state: GameState,
+        picks: Vec<usize>,
+        lobby: Vec<Ulid>,
+        players: HashMap<Ulid, PlayerData>,
+        winners: Vec<Winner>,
+        rounds: Vec<Round>,
+        suggestions: Vec<Suggestion>
+    ) -> Self {
+        let mut game = Self::new(id, size, items, options).with_info(info).with_access(access);
+        game.creator = creator;
+        *game.roles.write() = roles;
+        *game.state.write() = state;
+        *game.picks.write() = picks;
+        *game.lobby.write() = lobby;
+        *game.players.write() = players;
+        *game.winners.write() = winners;
+        game.round.store(rounds.len() as u32 + 1, Ordering::Relaxed);
+        *game.rounds.write() = rounds;
+        *game.suggestions.write() = suggestions;
+        game
+    }
+
+    pub fn with_creator(mut self, creator: Ulid) -> Self {
+        self.creator = Some(creator);
+        self
+    }
+
+    pub fn with_info(self, info: GameInfo) -> Self {
+        *self.info.write() = info;
+        self
+    }
+
+    pub fn with_access(self, access: Access) -> Self {
+        *self.access.write() = access;
+        self
+    }
+
+    pub fn get_items(&self) -> RwLockReadGuard<'_, Box<[Item]>> {
+        self.items.read()
+    }
+
+    pub fn get_picks(&self) -> RwLockReadGuard<'_, Vec<usize>> {
+        self.picks.read()
+    }
+
+    pub fn get_winners(&self) -> RwLockReadGuard<'_, Vec<Winner>> {
+        self.winners.read()
+    }
+
+    pub fn get_round(&self) -> u32 {
+        self.round.load(Ordering::Relaxed)
+    }
+
+    pub fn get_rounds(&self) -> RwLockReadGuard<'_, Vec<Round>> {
+        self.rounds.read()
+    }
+
+    pub fn get_suggestions(&self) -> RwLockReadGuard<'_, Vec<Suggestion>> {
+        self.suggestions.read()
+    }
+
+    pub fn get_id(&self) -> Ulid {
+        self.id
+    }
+
+    pub fn get_creator(&self) -> Option<Ulid> {
+        self.creator
+    }
+
+    pub fn get_roles(&self) -> RwLockReadGuard<'_, HashMap<Ulid, Role>> {
+        self.roles.read()
+    }
+
+    /// the role of `user` in the game, the creator is always its owner
+    pub fn role_of(&self, user: Ulid) -> Role {
+        if self.creator == Some(user) {
+            return Role::Owner;
+        }
+        self.roles.read().get(&user).copied().unwrap_or(Role::Player)
+    }
+
+    /// checks that the role of `user` in the game allows `permission`
+    pub fn check_role(&self, user: Ulid, permission: Permission) -> Result<(), GameError> {
+        match self.role_of(user).permits(permission) {
+            true => Ok(()),
+            false => Err(GameError::Forbidden),
+        }
+    }
+
+    /// checks that whoever `claims` belong to may do what `permission`
+    /// covers, admins may do anything
+    pub fn authorize(&self, claims: &Claims, permission: Permission) -> Result<(), GameError> {
+        if *claims.user_kind() == UserKind::Admin {
+            return Ok(());
+        }
+        self.check_role(claims.user_id(), permission)
+    }
+
+    pub fn get_access(&self) -> Access {
+        self.access.read().clone()
+    }
+
+    pub fn get_code(&self) -> Option<String> {
+        self.code.read().clone()
+    }
+
+    /// gives the game the join code `code`
+    ///
+    /// returns the one it had before
+    pub fn set_code(&self, code: String) -> Option<String> {
+        self.code.write().replace(code)
+    }
+
+    /// replaces the invite code of the game with `code`
+    ///
+    /// returns the one it had before
+    pub fn set_invite(&self, code: String) -> Option<String> {
+        self.access.write().invite.replace(code)
+    }
+
+    /// changes who can join the game, an empty `password` removes it
+    pub fn set_access(&self, visibility: Option<Visibility>, password: Option<String>) -> Result<(), CreateError> {
+        // hashing takes a while, so it's done before locking anything
+        let password_hash = match password.as_deref().map(str::trim) {
+            Some("") => Some(None),
+            Some(password) => {
+                access::validate_password(password)?;
+                Some(Some(access::hash_password(password)?))
+            },
+            None => None,
+        };
+        let mut access = self.access.write();
+        if let Some(visibility) = visibility {
+            access.visibility = visibility;
+        }
+        if let Some(password_hash) = password_hash {
+            access.password_hash = password_hash;
+        }
+        Ok(())
+    }
+
+    /// checks that whoever `claims` belong to can join or watch the game with
+    /// `key`, which private games need from everyone but admins, users with a
+    /// role in them and players who already joined
+    pub fn check_access(&self, claims: Option<&Claims>, key: Option<&str>) -> Result<(), GameError> {
+        let access = self.access.read();
+        if access.visibility != Visibility::Private || key.is_some_and(|k| access.accepts(k)) {
+            return Ok(());
+        }
+        drop(access);
+        let Some(claims) = claims else {
+            return Err(GameError::Private);
+        };
+        let user = claims.user_id();
+        if *claims.user_kind() == UserKind::Admin
+            || self.role_of(user) != Role::Player
+            || self.players.read().contains_key(&user)
+            || self.is_in_lobby(user) {
+            return Ok(());
         }
+        Err(GameError::Private)
     }
 
-    pub fn get_items(&self) -> &[Item] {
-        &self.items
+    /// gives `user` a role in the game, [Role::Player] takes away the one they
+    /// had
+    pub fn set_role(&self, user: Ulid, role: Role) -> Result<(), GameError> {
+        if role == Role::Owner || self.creator == Some(user) {
+            return Err(GameError::InvalidRole);
+        }
+        {
+            let mut roles = self.roles.write();
+            let changed = match role {
+                Role::Player => roles.remove(&user).is_some(),
+                role => roles.insert(user, role) != Some(role),
+            };
+            if !changed {
+                return Ok(());
+            }
+        }
+        self.send_event(ServerEvent::RoleChanged { user, role });
+        Ok(())
+    }
+
+    pub fn get_info(&self) -> GameInfo {
+        self.info.read().clone()
     }
 
     pub fn get_size(&self) -> u32 {
-        self.size
+        self.size.load(Ordering::Relaxed)
+    }
+
+    pub fn get_options(&self) -> Arc<GameOptions> {
+        Arc::clone(&self.options.read())
+    }
+
+    pub fn get_state(&self) -> GameState {
+        *self.state.read()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.get_state().is_over()
+    }
+
+    /// how long ago the game was created
+    pub fn age(&self) -> Duration {
+        SystemTime::now().duration_since(self.id.datetime()).unwrap_or_default()
+    }
+
+    /// how long ago anything last happened in the game
+    pub fn idle_time(&self) -> Duration {
+        self.last_activity.lock().elapsed()
+    }
+
+    fn touch(&self) {
+        *self.last_activity.lock() = Instant::now();
+    }
+
+    /// how many websockets are currently listening to the game
+    pub fn connection_count(&self) -> usize {
+        self.event_sender.receiver_count()
+    }
+
+    /// how many websockets are watching the game as spectators, they're part
+    /// of [Game::connection_count] too
+    pub fn spectator_count(&self) -> usize {
+        self.spectators.load(Ordering::Relaxed)
     }
 
-    pub fn subscribe_to(&self) -> Receiver<ServerEvent> {
+    /// how many players joined the game, including the ones waiting in the
+    /// lobby
+    pub fn player_count(&self) -> usize {
+        let lobby = self.lobby.read().len();
+        lobby + self.players.read().len()
+    }
+
+    /// takes a spot among the spectators of the game, of which there can be
+    /// at most `max`
+    pub fn spectate(self: &Arc<Self>, max: Option<usize>) -> Result<Spectator, GameError> {
+        self.spectators
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| match max {
+                Some(max) if count >= max => None,
+                _ => Some(count + 1),
+            })
+            .map_err(|_| GameError::TooManySpectators)?;
+        Ok(Spectator::new(Arc::clone(self)))
+    }
+
+    pub fn subscribe_to(&self) -> Receiver<GameEvent> {
         self.event_sender.subscribe()
     }
 
     pub fn send_event(&self, event: ServerEvent) {
-        self.event_sender.send(event);
+        // countdowns come from the auto picker and would keep games nobody
+        // plays in from ever being idle
+        if !matches!(event, ServerEvent::Countdown { .. }) {
+            self.touch();
+        }
+        // this only fails if nobody is listening, which is fine
+        let _ = self.event_sender.send(GameEvent { round: self.get_round(), event });
+    }
+
+    /// moves the game to the state `to`, telling everyone about it
+    ///
+    /// `state` is the guard of the game's own state, so the caller can do
+    /// other things before the transition without anything changing under it
+    fn transition(&self, state: &mut GameState, to: GameState) -> Result<(), GameError> {
+        if !state.can_transition_to(to) {
+            return Err(GameError::InvalidTransition { from: *state, to });
+        }
+        *state = to;
+        self.send_event(ServerEvent::StateChanged { state: to });
+        if to == GameState::Finished {
+            self.close_poll();
+            self.send_event(ServerEvent::GameOver);
+        }
+        Ok(())
+    }
+
+    /// starts the game, dealing a board to everyone who joined in the lobby
+    ///
+    /// returns the players that got a board
+    pub fn start(&self) -> Result<Vec<Ulid>, GameError> {
+        let mut state = self.state.write();
+        if *state != GameState::Lobby {
+            return Err(GameError::InvalidTransition { from: *state, to: GameState::Running });
+        }
+        let items_len = self.items.read().len();
+        let lobby = std::mem::take(&mut *self.lobby.write());
+        let mut players = self.players.write();
+        for id in &lobby {
+            self.deal_board(&mut players, *id, items_len);
+        }
+        drop(players);
+        self.transition(&mut state, GameState::Running)?;
+        Ok(lobby)
+    }
+
+    /// starts the next round of a finished game, with the same items and
+    /// players
+    ///
+    /// players get new boards unless [GameOptions::keep_boards] is set, their
+    /// marks are cleared either way
+    ///
+    /// returns the players whose boards were reset
+    pub fn new_round(&self) -> Result<Vec<Ulid>, GameError> {
+        let mut state = self.state.write();
+        if *state != GameState::Finished {
+            return Err(GameError::InvalidTransition { from: *state, to: GameState::Running });
+        }
+        let mut items = self.items.write();
+        for item in items.iter_mut() {
+            item.picked = false;
+        }
+        let mut players = self.players.write();
+        let ids: Vec<Ulid> = players.keys().copied().collect();
+        if self.get_options().keep_boards {
+            for player in players.values_mut() {
+                player.clear_marks();
+            }
+        } else {
+            players.clear();
+            for id in &ids {
+                self.deal_board(&mut players, *id, items.len());
+            }
+        }
+        drop((items, players));
+        self.picks.write().clear();
+        self.rejected_claims.lock().clear();
+        self.close_poll();
+        let winners = std::mem::take(&mut *self.winners.write());
+        self.rounds.write().push(Round { round: self.get_round(), winners });
+        self.round.fetch_add(1, Ordering::Relaxed);
+
+        self.send_event(ServerEvent::NewRound);
+        self.transition(&mut state, GameState::Running)?;
+        Ok(ids)
+    }
+
+    /// edits the game, which is only possible while it's in the lobby and no
+    /// boards were dealt yet
+    ///
+    /// the edited game is checked the same way a new one is, nothing changes
+    /// unless all of it is valid
+    pub fn update(&self, update: UpdateGameRequest) -> Result<(), UpdateError> {
+        let state = self.state.write();
+        if *state != GameState::Lobby {
+            return Err(GameError::AlreadyDealt.into());
+        }
+        let mut items = self.items.write();
+
+        let options = match update.options {
+            Some(patch) => {
+                let mut merged = serde_json::to_value(&*self.get_options()).map_err(|_| CreateError::InvalidOptions)?;
+                update::merge(&mut merged, patch);
+                let mut options: GameOptions = serde_json::from_value(merged).map_err(|_| CreateError::InvalidOptions)?;
+                if options.patterns.is_empty() {
+                    options.patterns = GameOptions::default().patterns;
+                }
+                Arc::new(options)
+            },
+            None => self.get_options(),
+        };
+        let current = self.get_info();
+        let info = GameInfo {
+            title: update.title.or(current.title),
+            description: update.description.or(current.description),
+            cover_url: update.cover_url.or(current.cover_url),
+            language: update.language.or(current.language),
+            tags: update.tags.unwrap_or(current.tags),
+        }.trimmed();
+        info.validate()?;
+        let size = update.size.unwrap_or(self.get_size());
+        let texts: Vec<&str> = match &update.items {
+            Some(new_items) => new_items.iter().map(String::as_str).collect(),
+            None => items.iter().map(|i| &*i.text).collect(),
+        };
+        create::validate(size, &texts, &options)?;
+
+        if let Some(new_items) = update.items {
+            *items = new_items.into_iter().map(Item::from).collect();
+        }
+        *self.info.write() = info;
+        self.size.store(size, Ordering::Relaxed);
+        *self.options.write() = options;
+        drop((state, items));
+
+        self.send_event(ServerEvent::GameUpdated);
+        Ok(())
+    }
+
+    pub fn pause(&self) -> Result<(), GameError> {
+        self.transition(&mut self.state.write(), GameState::Paused)
+    }
+
+    pub fn resume(&self) -> Result<(), GameError> {
+        let mut state = self.state.write();
+        if *state != GameState::Paused {
+            return Err(GameError::InvalidTransition { from: *state, to: GameState::Running });
+        }
+        self.transition(&mut state, GameState::Running)
+    }
+
+    pub fn end(&self) -> Result<(), GameError> {
+        self.transition(&mut self.state.write(), GameState::Finished)
+    }
+
+    pub fn archive(&self) -> Result<(), GameError> {
+        self.transition(&mut self.state.write(), GameState::Archived)
+    }
+
+    /// everyone's score across all rounds, from the highest to the lowest,
+    /// players who never won included
+    pub fn get_scores(&self) -> Vec<(Ulid, u32)> {
+        let options = self.get_options();
+        let mut scores: HashMap<Ulid, u32> = self.players.read().keys().map(|id| (*id, 0)).collect();
+        let winners = self.winners.read();
+        let rounds = self.rounds.read();
+        let round_scores = rounds.iter()
+            .map(|round| round.winners.as_slice())
+            .chain(std::iter::once(winners.as_slice()))
+            .flat_map(|winners| options.scoring.round_scores(winners));
+        for (id, points) in round_scores {
+            *scores.entry(id).or_default() += points;
+        }
+        let mut scores: Vec<(Ulid, u32)> = scores.into_iter().collect();
+        scores.sort_unstable_by(|(a_id, a), (b_id, b)| b.cmp(a).then(a_id.cmp(b_id)));
+        scores
+    }
+
+    /// tells everyone how the scores changed after the winners of the current
+    /// round went from `before` to `after`
+    fn send_score_changes(&self, before: &[Winner], after: &[Winner]) {
+        let options = self.get_options();
+        let scoring = &options.scoring;
+        let (before, after) = (scoring.round_scores(before), scoring.round_scores(after));
+        let rounds = self.rounds.read();
+        let past = |id: Ulid| -> u32 {
+            rounds.iter().map(|r| scoring.round_scores(&r.winners).get(&id).copied().unwrap_or_default()).sum()
+        };
+        let mut changes: Vec<ScoreChange> = before.keys().chain(after.keys())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter_map(|id| {
+                let (old, new) = (before.get(id).copied().unwrap_or_default(), after.get(id).copied().unwrap_or_default());
+                (old != new).then(|| ScoreChange {
+                    player: *id,
+                    delta: new as i64 - old as i64,
+                    score: past(*id) + new,
+                })
+            })
+            .collect();
+        if !changes.is_empty() {
+            changes.sort_unstable_by_key(|c| c.player);
+            self.send_event(ServerEvent::ScoresChanged { changes });
+        }
+    }
+
+    fn deal_board(&self, players: &mut HashMap<Ulid, PlayerData>, id: Ulid, items_len: usize) {
+        let (options, size) = (self.get_options(), self.get_size());
+        let player = if options.diverse_boards {
+            let mut usage = vec![0; items_len];
+            for idx in players.values().flat_map(|p| p.items()) {
+                usage[idx] += 1;
+            }
+            PlayerData::new_diverse(size, items_len, options.free_space, &usage)
+        } else {
+            PlayerData::new_random(size, items_len, options.free_space)
+        };
+        players.insert(id, player);
+    }
+
+    /// adds the player with the user ID `id` to the game, does nothing if they
+    /// already joined
+    ///
+    /// players that join in the lobby only get their board once the game
+    /// starts, everyone else gets theirs right away
+    ///
+    /// returns whether the player was new
+    pub fn add_new_player(&self, id: Ulid) -> Result<bool, GameError> {
+        self.check_role(id, Permission::Play)?;
+        let state = self.state.read();
+        if state.is_over() {
+            return Err(GameError::GameOver);
+        }
+        let items_len = self.items.read().len();
+        let mut lobby = self.lobby.write();
+        let mut players = self.players.write();
+        if players.contains_key(&id) || lobby.contains(&id) {
+            return Ok(false);
+        }
+        self.touch();
+        if *state == GameState::Lobby {
+            lobby.push(id);
+        } else {
+            self.deal_board(&mut players, id, items_len);
+        }
+        Ok(true)
+    }
+
+    /// adds the item `text` suggested by the player with the user ID `id` to
+    /// the moderation queue
+    ///
+    /// suggestions are only taken in the lobby, from players who joined it,
+    /// and every player can make at most [GameOptions::suggestion_limit]
+    pub fn suggest_item(&self, id: Ulid, text: &str) -> Result<Suggestion, GameError> {
+        self.check_role(id, Permission::Play)?;
+        let state = self.state.read();
+        let limit = self.get_options().suggestion_limit;
+        if *state != GameState::Lobby || limit == 0 {
+            return Err(GameError::SuggestionsClosed);
+        }
+        let text = text.trim();
+        match text.chars().count() {
+            0 => return Err(GameError::EmptyItem),
+            n if n > MAX_ITEM_LENGTH => return Err(GameError::ItemTooLong),
+            _ => (),
+        }
+        let items = self.items.read();
+        if !self.lobby.read().contains(&id) {
+            return Err(GameError::NotInGame);
+        }
+        let mut suggestions = self.suggestions.write();
+        if suggestions.iter().filter(|s| s.player == id).count() >= limit as usize {
+            return Err(GameError::TooManySuggestions);
+        }
+        let normalized = Item::normalize(text);
+        let duplicate = items.iter().map(|i| &*i.text)
+            .chain(suggestions.iter().map(|s| s.text.as_str()))
+            .any(|other| Item::normalize(other) == normalized);
+        if duplicate {
+            return Err(GameError::DuplicateItem);
+        }
+
+        let suggestion = Suggestion {
+            id: Ulid::new(),
+            player: id,
+            text: text.to_owned(),
+            status: SuggestionStatus::Pending,
+        };
+        suggestions.push(suggestion.clone());
+        self.send_event(ServerEvent::Suggested { suggestion: suggestion.clone() });
+        Ok(suggestion)
+    }
+
+    /// approves or rejects the pending suggestion `suggestion`, approved ones
+    /// are added to the game's items
+    ///
+    /// returns the index of the new item if it was approved
+    pub fn review_suggestion(&self, suggestion: Ulid, approved: bool) -> Result<Option<usize>, GameError> {
+        let state = self.state.read();
+        if *state != GameState::Lobby {
+            return Err(GameError::SuggestionsClosed);
+        }
+        let mut items = self.items.write();
+        let mut suggestions = self.suggestions.write();
+        let reviewed = suggestions.iter_mut()
+            .find(|s| s.id == suggestion && s.status == SuggestionStatus::Pending)
+            .ok_or(GameError::NoSuchSuggestion)?;
+        let idx = match approved {
+            true => {
+                let mut new_items = std::mem::take(&mut *items).into_vec();
+                new_items.push(reviewed.text.clone().into());
+                *items = new_items.into_boxed_slice();
+                reviewed.status = SuggestionStatus::Approved;
+                Some(items.len() - 1)
+            },
+            false => {
+                reviewed.status = SuggestionStatus::Rejected;
+                None
+            },
+        };
+        self.send_event(ServerEvent::SuggestionReviewed { suggestion, approved, idx });
+        Ok(idx)
+    }
+
+    /// whether the player with the user ID `id` is waiting in the lobby
+    pub fn is_in_lobby(&self, id: Ulid) -> bool {
+        self.lobby.read().contains(&id)
+    }
+
+    /// the board of the player with the user ID `id`
+    pub fn get_player(&self, id: Ulid) -> Option<PlayerData> {
+        self.players.read().get(&id).cloned()
+    }
+
+    /// why the player with the user ID `id` has no board
+    ///
+    /// this locks the lobby, so no lock on the players can be held
+    pub(super) fn missing_player(&self, id: Ulid) -> GameError {
+        match self.lobby.read().contains(&id) {
+            true => GameError::NotDealtYet,
+            false => GameError::NotInGame,
+        }
+    }
+
+    /// marks or unmarks the cell at `cell` on the board of the player with
+    /// the user ID `id`
+    ///
+    /// with [GameOptions::strict_marks] only picked items can be marked
+    pub fn mark_cell(&self, id: Ulid, cell: usize, marked: bool) -> Result<(), GameError> {
+        self.check_role(id, Permission::Play)?;
+        let state = self.state.read();
+        if state.is_over() {
+            return Err(GameError::GameOver);
+        }
+        let items = self.items.read();
+        let mut players = self.players.write();
+        let Some(player) = players.get_mut(&id) else {
+            drop(players);
+            return Err(self.missing_player(id));
+        };
+        let idx = player.board().get(cell).ok_or(GameError::NoSuchCell)?;
+        if marked && self.get_options().strict_marks && idx.is_some_and(|idx| !items[idx].picked) {
+            return Err(GameError::NotPicked);
+        }
+        player.set_mark(cell, marked).ok_or(GameError::NoSuchCell)
+    }
+
+    fn check_running(state: GameState) -> Result<(), GameError> {
+        match state {
+            GameState::Running => Ok(()),
+            s if s.is_over() => Err(GameError::GameOver),
+            _ => Err(GameError::NotRunning),
+        }
+    }
+
+    /// marks the item at `idx` as picked and notifies everyone listening,
+    /// ending the round if that completed a winning pattern for anyone and the
+    /// round doesn't go on after its first win
+    pub fn pick_item(&self, idx: usize) -> Result<(), GameError> {
+        // the state is locked for the whole pick so no other picks can sneak
+        // in while the winners are announced
+        let mut state = self.state.write();
+        Self::check_running(*state)?;
+        self.pick_locked(&mut state, idx)
+    }
+
+    /// picks the item at `idx`, whether the game's state allows that is up to
+    /// the caller
+    fn pick_locked(&self, state: &mut GameState, idx: usize) -> Result<(), GameError> {
+        let mut items = self.items.write();
+        let item = items.get_mut(idx).ok_or(GameError::NoSuchItem)?;
+        if item.picked {
+            return Err(GameError::AlreadyPicked);
+        }
+        item.picked = true;
+        self.picks.write().push(idx);
+        self.rejected_claims.lock().clear();
+
+        self.send_event(ServerEvent::NewBall { idx });
+
+        let mut poll = self.poll.lock();
+        if let Some(decided) = poll.take_if(|poll| poll.idx == idx) {
+            self.send_event(ServerEvent::PollClosed { poll: decided.id, idx, picked: true });
+        }
+        drop(poll);
+
+        let options = self.get_options();
+        if options.win_mode != WinMode::Auto {
+            return Ok(());
+        }
+
+        let players = self.players.read();
+        let picks = self.picks.read().len();
+        let mut winners = self.winners.write();
+        let new_winners: Vec<Winner> = players.iter()
+            .flat_map(|(id, player)| {
+                win::find_wins(player.board(), self.get_size(), &items, &options.patterns)
+                    .into_iter()
+                    .filter(|win| !winners.iter().any(|w| w.player == *id && w.win == *win))
+                    .map(|win| Winner { player: *id, win, picks })
+            })
+            .collect();
+
+        if !new_winners.is_empty() {
+            for Winner { player, win: Win { pattern, line }, .. } in new_winners.iter().cloned() {
+                self.send_event(ServerEvent::Winner { player, pattern, line });
+            }
+            let before = winners.clone();
+            winners.extend(new_winners);
+            self.send_score_changes(&before, &winners);
+            if *state != GameState::Finished && self.round_over(&options, &winners, players.len()) {
+                self.transition(state, GameState::Finished)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// picks a random item that wasn't picked yet
+    ///
+    /// returns the picked item, `None` if every item was already picked
+    pub fn pick_random(&self) -> Result<Option<usize>, GameError> {
+        let mut state = self.state.write();
+        Self::check_running(*state)?;
+        let unpicked: Vec<usize> = self.items.read().iter()
+            .enumerate()
+            .filter(|(_, item)| !item.picked)
+            .map(|(idx, _)| idx)
+            .collect();
+        let Some(idx) = unpicked.choose(&mut rand::thread_rng()).copied() else {
+            return Ok(None);
+        };
+        self.pick_locked(&mut state, idx)?;
+        Ok(Some(idx))
+    }
+
+    /// opens a vote on whether the item at `idx` should be picked, it gets
+    /// picked as soon as enough players vote for it
+    ///
+    /// closing the poll once its window is over is up to the caller, see
+    /// [Game::expire_poll]
+    pub fn open_poll(&self, idx: usize) -> Result<OpenedPoll, GameError> {
+        let state = self.state.read();
+        Self::check_running(*state)?;
+        match self.items.read().get(idx) {
+            Some(item) if item.picked => return Err(GameError::AlreadyPicked),
+            Some(_) => (),
+            None => return Err(GameError::NoSuchItem),
+        }
+        let voting = &self.get_options().voting;
+        let needed = voting.needed(self.players.read().len());
+        let mut poll = self.poll.lock();
+        if poll.is_some() {
+            return Err(GameError::PollOpen);
+        }
+        let opened = poll.insert(Poll::new(idx, needed));
+        self.send_event(ServerEvent::PollOpened {
+            poll: opened.id,
+            idx,
+            needed,
+            seconds: voting.window.into(),
+        });
+        Ok(OpenedPoll { poll: opened.id, needed })
+    }
+
+    /// counts the vote of the player with the user ID `id` in the poll
+    /// `poll`, everyone only gets one vote per poll
+    ///
+    /// returns whether the vote got the item picked
+    pub fn vote(&self, id: Ulid, poll: Ulid, yes: bool) -> Result<bool, GameError> {
+        self.check_role(id, Permission::Play)?;
+        let mut state = self.state.write();
+        Self::check_running(*state)?;
+        if !self.players.read().contains_key(&id) {
+            return Err(self.missing_player(id));
+        }
+        let mut open = self.poll.lock();
+        let current = open.as_mut().filter(|p| p.id == poll).ok_or(GameError::NoSuchPoll)?;
+        current.vote(id, yes).ok_or(GameError::AlreadyVoted)?;
+        let (yes, no) = current.tally();
+        let (idx, needed, passed) = (current.idx, current.needed, current.passed());
+        self.send_event(ServerEvent::VoteTally { poll, yes, no, needed });
+        drop(open);
+
+        if passed {
+            // this closes the poll as well
+            self.pick_locked(&mut state, idx)?;
+        }
+        Ok(passed)
+    }
+
+    /// closes the open poll without picking anything, if there is one
+    fn close_poll(&self) {
+        if let Some(closed) = self.poll.lock().take() {
+            self.send_event(ServerEvent::PollClosed { poll: closed.id, idx: closed.idx, picked: false });
+        }
+    }
+
+    /// closes the poll `poll` without picking anything, if it is still open
+    pub fn expire_poll(&self, poll: Ulid) {
+        if let Some(expired) = self.poll.lock().take_if(|p| p.id == poll) {
+            self.send_event(ServerEvent::PollClosed { poll, idx: expired.idx, picked: false });
+        }
+    }
+
+    /// picks can be corrected while the game is going on, and after it was
+    /// won since the win might not hold up after the correction
+    fn check_correctable(&self, state: GameState) -> Result<(), GameError> {
+        match state {
+            GameState::Running | GameState::Paused => Ok(()),
+            GameState::Finished if !self.winners.read().is_empty() => Ok(()),
+            GameState::Lobby => Err(GameError::NotRunning),
+            _ => Err(GameError::GameOver),
+        }
+    }
+
+    /// takes back the pick of the item at `idx`, revoking every win that
+    /// doesn't hold up without it
+    ///
+    /// a game that was finished by the revoked wins is running again after
+    /// this
+    pub fn unpick_item(&self, idx: usize) -> Result<(), GameError> {
+        let mut state = self.state.write();
+        self.check_correctable(*state)?;
+        self.unpick_locked(&mut state, idx)
+    }
+
+    /// replaces the pick of the item at `old` with the item at `new`, as if
+    /// `new` had been picked in the first place
+    pub fn replace_pick(&self, old: usize, new: usize) -> Result<(), GameError> {
+        let mut state = self.state.write();
+        self.check_correctable(*state)?;
+        {
+            let items = self.items.read();
+            match (items.get(old), items.get(new)) {
+                (Some(old), Some(_)) if !old.picked => return Err(GameError::NotPicked),
+                (Some(_), Some(new)) if new.picked => return Err(GameError::AlreadyPicked),
+                (Some(_), Some(_)) => (),
+                _ => return Err(GameError::NoSuchItem),
+            }
+        }
+        self.unpick_locked(&mut state, old)?;
+        self.pick_locked(&mut state, new)
+    }
+
+    fn unpick_locked(&self, state: &mut GameState, idx: usize) -> Result<(), GameError> {
+        let mut items = self.items.write();
+        let item = items.get_mut(idx).ok_or(GameError::NoSuchItem)?;
+        if !item.picked {
+            return Err(GameError::NotPicked);
+        }
+        item.picked = false;
+        let mut players = self.players.write();
+        let options = self.get_options();
+        if options.strict_marks {
+            for player in players.values_mut() {
+                player.unmark_item(idx);
+            }
+        }
+        let mut picks = self.picks.write();
+        picks.retain(|pick| *pick != idx);
+        self.rejected_claims.lock().clear();
+
+        self.send_event(ServerEvent::Unpicked { idx });
+
+        let mut winners = self.winners.write();
+        let before = winners.clone();
+        winners.retain_mut(|winner| {
+            let holds = players.get(&winner.player)
+                .is_some_and(|p| winner.win.holds(p.board(), self.get_size(), &items));
+            if holds {
+                winner.picks = winner.picks.min(picks.len());
+            } else {
+                self.send_event(ServerEvent::WinRevoked {
+                    player: winner.player,
+                    pattern: winner.win.pattern.clone(),
+                    line: winner.win.line,
+                    reason: RevokeReason::PickUndone { idx },
+                });
+            }
+            holds
+        });
+
+        self.send_score_changes(&before, &winners);
+
+        if winners.len() < before.len() && *state == GameState::Finished && !self.round_over(&options, &winners, players.len()) {
+            self.transition(state, GameState::Running)?;
+        }
+        Ok(())
+    }
+
+    /// whether a round with `player_count` players is over after `winners`
+    /// won it, which is right after the first win unless later wins still
+    /// score, then it goes on until every player won all they can
+    fn round_over(&self, options: &GameOptions, winners: &[Winner], player_count: usize) -> bool {
+        if winners.is_empty() {
+            return false;
+        }
+        if !options.scoring.keeps_round_running(&options.patterns) {
+            return true;
+        }
+        let most = win::max_wins(self.get_size(), &options.patterns);
+        let mut wins: HashMap<Ulid, usize> = HashMap::new();
+        for winner in winners {
+            *wins.entry(winner.player).or_default() += 1;
+        }
+        wins.values().filter(|count| **count >= most).count() >= player_count
     }
 
-    pub fn add_new_player(&self, id: Ulid) {
-        self.players.write().insert(id, PlayerData::new_random(self.size, self.items.len()));
+    /// checks the board of the player with the user ID `id` for wins that
+    /// weren't claimed yet, ending the round if that was the last one that
+    /// mattered and telling everyone it was a false alarm if there are none
+    ///
+    /// returns the newly claimed wins
+    pub fn claim_bingo(&self, id: Ulid) -> Result<Vec<Win>, GameError> {
+        self.check_role(id, Permission::Play)?;
+        let mut state = self.state.write();
+        Self::check_running(*state)?;
+        let items = self.items.read();
+        let options = self.get_options();
+        let (wins, player_count) = {
+            let players = self.players.read();
+            let wins = players.get(&id)
+                .map(|player| win::find_wins(player.board(), self.get_size(), &items, &options.patterns));
+            (wins, players.len())
+        };
+        let Some(wins) = wins else {
+            return Err(self.missing_player(id));
+        };
+
+        if wins.is_empty() {
+            if self.rejected_claims.lock().insert(id) {
+                self.send_event(ServerEvent::ClaimRejected { player: id });
+            }
+            return Err(GameError::NoBingo);
+        }
+
+        let picks = self.picks.read().len();
+        let mut winners = self.winners.write();
+        let new_wins: Vec<Win> = wins.into_iter()
+            .filter(|win| !winners.iter().any(|w| w.player == id && w.win == *win))
+            .collect();
+        if new_wins.is_empty() {
+            return Err(GameError::AlreadyWon);
+        }
+        let before = winners.clone();
+        for win in &new_wins {
+            self.send_event(ServerEvent::Winner { player: id, pattern: win.pattern.clone(), line: win.line });
+            winners.push(Winner { player: id, win: win.clone(), picks });
+        }
+        self.send_score_changes(&before, &winners);
+        let over = self.round_over(&options, &winners, player_count);
+        drop(winners);
+        if over {
+            self.transition(&mut state, GameState::Finished)?;
+        }
+        Ok(new_wins)
     }
 }
 
@@ -76,6 +1074,17 @@ pub struct Item {
     picked: bool,
 }
 
+/// the most characters the text of an item can have
+pub const MAX_ITEM_LENGTH: usize = 100;
+
+impl Item {
+    /// `text` with its case and whitespace evened out, items whose texts
+    /// normalize to the same thing count as duplicates
+    pub fn normalize(text: &str) -> String {
+        text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+    }
+}
+
 impl From<String> for Item {
     fn from(value: String) -> Self {
         Self {
@@ -87,5 +1096,27 @@ impl From<String> for Item {
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/get").get(get::get_game))
-        .service(web::resource("/create").post(create::create_game));
+        .service(web::resource("/create").post(create::create_game))
+        .service(web::resource("/invite/{code}").get(access::resolve_invite))
+        .service(web::resource("/{id}").patch(update::update_game))
+        .service(web::resource("/{id}/join").post(join::join_game))
+        .service(web::resource("/{id}/board").get(board::get_board))
+        .service(web::resource("/{id}/pick").post(pick::pick_item))
+        .service(web::resource("/{id}/unpick").post(pick::unpick_item))
+        .service(web::resource("/{id}/replace").post(pick::replace_pick))
+        .service(web::resource("/{id}/claim").post(claim::claim_bingo))
+        .service(web::resource("/{id}/mark").post(mark::mark_cell))
+        .service(web::resource("/{id}/suggestions").post(suggest::suggest_item).get(suggest::get_suggestions))
+        .service(web::resource("/{id}/suggestions/review").post(suggest::review_suggestion))
+        .service(web::resource("/{id}/poll").post(vote::open_poll))
+        .service(web::resource("/{id}/access").patch(access::update_access))
+        .service(web::resource("/{id}/invite").get(access::get_invite).post(access::rotate_invite))
+        .service(web::resource("/{id}/roles").get(role::get_roles).post(role::grant_role))
+        .service(web::resource("/{id}/roles/{login}").delete(role::revoke_role))
+        .service(web::resource("/{id}/start").post(lifecycle::start_game))
+        .service(web::resource("/{id}/pause").post(lifecycle::pause_game))
+        .service(web::resource("/{id}/resume").post(lifecycle::resume_game))
+        .service(web::resource("/{id}/end").post(lifecycle::end_game))
+        .service(web::resource("/{id}/round").post(round::new_round))
+        .service(web::resource("/{id}/leaderboard").get(score::get_leaderboard));
 }
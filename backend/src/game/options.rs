@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+use super::{score::Scoring, vote::Voting, win::WinPattern};
+
+/// settings a host can pick when creating a game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct GameOptions {
+    /// the patterns that win the game, any one of them is enough
+    #[cfg_attr(feature="swagger-ui", schema(example = json!(["line", "four_corners"])))]
+    pub patterns: Vec<WinPattern>,
+    /// whether the center cell of every board is marked from the start
+    pub free_space: bool,
+    /// text shown on the free space, `"FREE"` if left out
+    #[cfg_attr(feature="swagger-ui", schema(example = "FREE"))]
+    pub free_space_label: Option<String>,
+    /// try to give every player a board that shares as few items as possible
+    /// with everyone else's
+    pub diverse_boards: bool,
+    /// whether players can only mark cells whose items were picked, instead of
+    /// marking anything and leaving it to the host to check
+    pub strict_marks: bool,
+    /// whether players keep their boards when a new round starts, instead of
+    /// getting new ones
+    pub keep_boards: bool,
+    /// how many items every player can suggest while the game is in the
+    /// lobby, 0 turns suggestions off
+    #[cfg_attr(feature="swagger-ui", schema(example = 3))]
+    pub suggestion_limit: u32,
+    /// whether wins are found by the server or have to be claimed by players
+    pub win_mode: WinMode,
+    /// seconds between items being picked automatically, they're only picked
+    /// by the host if left out
+    #[cfg_attr(feature="swagger-ui", schema(example = 30, minimum = 1))]
+    pub auto_pick_interval: Option<u32>,
+    /// how many points wins are worth on the leaderboard
+    pub scoring: Scoring,
+    /// how players vote on the items that get picked
+    pub voting: Voting,
+}
+
+/// how the winners of a game are found
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum WinMode {
+    /// every board is checked after each pick and the round ends as soon as
+    /// someone wins, or everyone if later lines score points
+    #[default]
+    Auto,
+    /// players have to claim their bingo themselves, which is then checked
+    Claim,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            patterns: vec![WinPattern::Line],
+            free_space: false,
+            free_space_label: None,
+            diverse_boards: false,
+            strict_marks: false,
+            keep_boards: false,
+            suggestion_limit: 3,
+            win_mode: WinMode::Auto,
+            auto_pick_interval: None,
+            scoring: Scoring::default(),
+            voting: Voting::default(),
+        }
+    }
+}
+
+impl GameOptions {
+    /// the text shown on the free space, if the game has one
+    pub fn free_space_label(&self) -> Option<&str> {
+        self.free_space.then(|| self.free_space_label.as_deref().unwrap_or("FREE"))
+    }
+
+    /// how many items are needed to fill a board of size `size`
+    pub fn items_needed(&self, size: u32) -> usize {
+        size.pow(2) as usize - self.free_space as usize
+    }
+}
@@ -0,0 +1,131 @@
+use actix_web::{web::{Data, Json, Path}, HttpResponse};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager, role::Permission};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct PickRequest {
+    /// index of the item in the game's `items`
+    #[cfg_attr(feature="swagger-ui", schema(example = 3))]
+    pub(super) idx: usize
+}
+
+/// pick an item, marking it on every board that contains it
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/pick",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = PickRequest,
+    responses(
+        (status = 204, description = "The item was picked and all players were notified"),
+        (status = 400, description = "There is no item with that index", body = GameError),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The item had already been picked or the game is over", body = GameError)
+    )
+))]
+pub async fn pick_item(
+    claims: Claims,
+    id: Path<Ulid>,
+    pick: Json<PickRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::RunBoard)?;
+
+    game.pick_item(pick.idx)?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct ReplaceRequest {
+    /// index of the item that was picked by mistake
+    #[cfg_attr(feature="swagger-ui", schema(example = 3))]
+    pub(super) old: usize,
+    /// index of the item that should have been picked instead
+    #[cfg_attr(feature="swagger-ui", schema(example = 4))]
+    pub(super) new: usize,
+}
+
+/// take back the pick of an item, revoking any wins that needed it
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/unpick",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = PickRequest,
+    responses(
+        (status = 204, description = "The pick was taken back and all players were notified"),
+        (status = 400, description = "There is no item with that index", body = GameError),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The item wasn't picked or the game can't be corrected anymore", body = GameError)
+    )
+))]
+pub async fn unpick_item(
+    claims: Claims,
+    id: Path<Ulid>,
+    pick: Json<PickRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::RunBoard)?;
+
+    game.unpick_item(pick.idx)?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
+
+/// replace a pick with another item, as if that one had been picked instead
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/replace",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = ReplaceRequest,
+    responses(
+        (status = 204, description = "The pick was replaced and all players were notified"),
+        (status = 400, description = "There is no item with one of the indices", body = GameError),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The old item wasn't picked, the new one already was or the game can't be corrected anymore", body = GameError)
+    )
+))]
+pub async fn replace_pick(
+    claims: Claims,
+    id: Path<Ulid>,
+    replace: Json<ReplaceRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::RunBoard)?;
+
+    game.replace_pick(replace.old, replace.new)?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
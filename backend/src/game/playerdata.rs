@@ -1,31 +1,109 @@
-use rand::Rng;
+use rand::{seq::index, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// how many boards are generated to pick the most diverse one from
+const DIVERSITY_CANDIDATES: usize = 16;
 
 #[derive(Debug, Clone)]
 pub struct PlayerData {
-    /// indices of items
-    board: Box<[usize]>
+    /// the seed the board was generated from
+    seed: u64,
+    /// indices of items, `None` being the free space
+    board: Box<[Option<usize>]>,
+    /// which cells of `board` the player marked, the free space always is
+    marks: Box<[bool]>,
 }
 
 impl PlayerData {
-    pub fn new_random(board_size: u32, items_len: usize) -> Self {
+    pub fn new_random(board_size: u32, items_len: usize, free_space: bool) -> Self {
+        Self::from_seed(rand::thread_rng().gen(), board_size, items_len, free_space)
+    }
+
+    /// generates boards from a few random seeds and keeps the one sharing the
+    /// least items with the boards already dealt
+    ///
+    /// `usage` is how many boards each item is already on
+    pub fn new_diverse(board_size: u32, items_len: usize, free_space: bool, usage: &[u32]) -> Self {
         let mut rng = rand::thread_rng();
-        let mut items = Vec::with_capacity(items_len);
-        for i in 0..items.capacity() {
-            items.push(i)
-        }
-        for i in 0..items.len() {
-            let random = rng.gen_range(0..(items.len() - 1));
-            items.swap(random, items_len - 1 - i)
+        (0..DIVERSITY_CANDIDATES)
+            .map(|_| Self::from_seed(rng.gen(), board_size, items_len, free_space))
+            .min_by_key(|p| p.items().map(|i| usage.get(i).copied().unwrap_or_default()).sum::<u32>())
+            .unwrap_or_else(|| Self::new_random(board_size, items_len, free_space))
+    }
+
+    /// generates the board for `seed`, the same seed and arguments always
+    /// result in the same board, and no item is ever on it twice
+    pub fn from_seed(seed: u64, board_size: u32, items_len: usize, free_space: bool) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let cells = (board_size * board_size) as usize;
+
+        let mut picked = index::sample(&mut rng, items_len, cells - free_space as usize).into_iter();
+
+        let board = (0..cells)
+            .map(|cell| match free_space && cell == cells / 2 {
+                true => None,
+                false => picked.next(),
+            })
+            .collect();
+
+        Self::from_parts(seed, board, None)
+    }
+
+    /// puts a board that was saved before back together, `marks` being left
+    /// out if nothing was ever marked
+    pub fn from_parts(seed: u64, board: Box<[Option<usize>]>, marks: Option<Box<[bool]>>) -> Self {
+        let marks = board.iter().enumerate()
+            .map(|(cell, idx)| idx.is_none() || marks.as_ref().is_some_and(|m| m.get(cell).copied().unwrap_or_default()))
+            .collect();
+        Self {
+            seed,
+            board,
+            marks
         }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// indices of the items on this board, row by row, `None` being the free
+    /// space
+    pub fn board(&self) -> &[Option<usize>] {
+        &self.board
+    }
+
+    /// which cells the player marked, row by row
+    pub fn marks(&self) -> &[bool] {
+        &self.marks
+    }
 
-        let mut board = Vec::with_capacity((board_size * board_size) as usize);
+    /// marks or unmarks the cell at `cell`, the free space can't be unmarked
+    ///
+    /// returns `None` if there is no such cell
+    pub fn set_mark(&mut self, cell: usize, marked: bool) -> Option<()> {
+        let idx = self.board.get(cell)?;
+        self.marks[cell] = idx.is_none() || marked;
+        Some(())
+    }
 
-        for _ in 0..board.capacity() {
-            board.push(rng.gen_range(0..items_len));
+    /// unmarks every cell except the free space
+    pub fn clear_marks(&mut self) {
+        for (cell, mark) in self.board.iter().zip(self.marks.iter_mut()) {
+            *mark = cell.is_none();
         }
+    }
 
-        Self {
-            board: board.into()
+    /// unmarks every cell with the item at `idx`
+    pub fn unmark_item(&mut self, idx: usize) {
+        for (cell, mark) in self.board.iter().zip(self.marks.iter_mut()) {
+            if *cell == Some(idx) {
+                *mark = false;
+            }
         }
     }
+
+    /// indices of the items on this board, skipping the free space
+    pub fn items(&self) -> impl Iterator<Item = usize> + '_ {
+        self.board.iter().flatten().copied()
+    }
 }
@@ -0,0 +1,180 @@
+use actix_web::{web::{Data, Json, Path}, HttpResponse};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{access::AccessKey, error::GameError, manager::GamesManager};
+
+/// what a user is to a game, users without a role are players
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum Role {
+    /// whoever created the game, this role can't be granted
+    Owner,
+    CoHost,
+    Moderator,
+    Player,
+    /// can watch the game but not play it
+    Spectator,
+}
+
+/// what a role allows, every permission includes the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    /// join, mark cells, claim bingos, vote and suggest items
+    Play,
+    /// pick items, open polls and review suggestions
+    RunBoard,
+    /// start, pause, end and edit the game and start new rounds
+    Host,
+    /// grant and revoke roles
+    ManageRoles,
+}
+
+impl Role {
+    /// the most the role allows, `None` if it doesn't allow anything
+    fn highest(self) -> Option<Permission> {
+        match self {
+            Role::Owner => Some(Permission::ManageRoles),
+            Role::CoHost => Some(Permission::Host),
+            Role::Moderator => Some(Permission::RunBoard),
+            Role::Player => Some(Permission::Play),
+            Role::Spectator => None,
+        }
+    }
+
+    pub fn permits(self, permission: Permission) -> bool {
+        self.highest().is_some_and(|highest| permission <= highest)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct GrantRequest {
+    /// the twitch login of the user, they have to have logged in before
+    #[cfg_attr(feature="swagger-ui", schema(example = "julialuxel"))]
+    pub(super) login: String,
+    /// any role but [Role::Owner]
+    pub(super) role: Role,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct RoleEntry {
+    #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
+    user: Ulid,
+    /// the user's twitch display name, if it is known
+    #[cfg_attr(feature="swagger-ui", schema(example = "JuliaLuxel"))]
+    display_name: Option<String>,
+    role: Role,
+}
+
+/// get everyone who has a role in the game other than [Role::Player], the
+/// owner first
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    get,
+    path = "/game/{id}/roles",
+    tag = "Game",
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game"),
+        AccessKey
+    ),
+    responses(
+        (status = 200, description = "The roles in the game", body = [RoleEntry]),
+        (status = 403, description = "The game is private and the key was missing or wrong", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError)
+    )
+))]
+pub async fn get_roles(
+    claims: Option<Claims>,
+    id: Path<Ulid>,
+    key: AccessKey,
+    games_manager: Data<GamesManager>
+) -> Result<Json<Vec<RoleEntry>>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.check_access(claims.as_ref(), key.0.as_deref())?;
+
+    let roles: Vec<(Ulid, Role)> = game.get_creator().map(|owner| (owner, Role::Owner)).into_iter()
+        .chain(game.get_roles().iter().map(|(user, role)| (*user, *role)))
+        .collect();
+    let ids: Vec<Ulid> = roles.iter().map(|(user, _)| *user).collect();
+    let mut names = games_manager.display_names(&ids).await;
+
+    let entries = roles.into_iter()
+        .map(|(user, role)| RoleEntry { user, display_name: names.remove(&user), role })
+        .collect();
+
+    return Ok(Json(entries));
+}
+
+/// give a user a role in the game, replacing the one they had
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/roles",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = GrantRequest,
+    responses(
+        (status = 204, description = "The role was granted and everyone was notified"),
+        (status = 400, description = "The owner's role can't be granted or changed", body = GameError),
+        (status = 403, description = "Only the owner of the game can do this", body = GameError),
+        (status = 404, description = "No game with that ULID or no user with that login was found", body = GameError)
+    )
+))]
+pub async fn grant_role(
+    claims: Claims,
+    id: Path<Ulid>,
+    grant: Json<GrantRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::ManageRoles)?;
+
+    let user = games_manager.find_user(&grant.login).await.ok_or(GameError::NoSuchUser)?;
+    game.set_role(user, grant.role)?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
+
+/// take a user's role in the game away, making them a player again
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    delete,
+    path = "/game/{id}/roles/{login}",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game"),
+        ("login" = String, Path, description = "the twitch login of the user")
+    ),
+    responses(
+        (status = 204, description = "The role was revoked and everyone was notified"),
+        (status = 400, description = "The owner's role can't be changed", body = GameError),
+        (status = 403, description = "Only the owner of the game can do this", body = GameError),
+        (status = 404, description = "No game with that ULID or no user with that login was found", body = GameError)
+    )
+))]
+pub async fn revoke_role(
+    claims: Claims,
+    path: Path<(Ulid, String)>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let (id, login) = path.into_inner();
+    let game = games_manager.get_game(id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::ManageRoles)?;
+
+    let user = games_manager.find_user(&login).await.ok_or(GameError::NoSuchUser)?;
+    game.set_role(user, Role::Player)?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
@@ -0,0 +1,49 @@
+use actix_web::{web::{Data, Path}, HttpResponse};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager, role::Permission, win::Winner};
+
+/// a round that was played before the current one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Round {
+    #[cfg_attr(feature="swagger-ui", schema(example = 1))]
+    pub round: u32,
+    /// everyone who won the round, in the order they won
+    pub winners: Vec<Winner>,
+}
+
+/// start the next round of a finished game, keeping its items and players
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/round",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 204, description = "The next round started"),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The current round isn't finished", body = GameError)
+    )
+))]
+pub async fn new_round(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    let players = game.new_round()?;
+    games_manager.save_players(&game, players);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
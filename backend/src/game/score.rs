@@ -0,0 +1,155 @@
+use actix_web::web::{Data, Json, Path};
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{access::AccessKey, error::GameError, manager::GamesManager, win::{WinPattern, Winner}};
+
+/// how many points wins are worth, winning patterns other than
+/// [WinPattern::Blackout] all count as lines
+///
+/// every line a player completes in a round is a win of its own, and so is a
+/// blackout after them, as long as the round keeps going
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Scoring {
+    /// points for the first line of a round, everyone who got one with the
+    /// same pick gets them once
+    #[cfg_attr(feature="swagger-ui", schema(example = 100))]
+    pub first_line: u32,
+    /// points for every line after the first one, 0 by default so rounds
+    /// without a blackout to play for end at their first line
+    #[cfg_attr(feature="swagger-ui", schema(default = 0, example = 50))]
+    pub line: u32,
+    #[cfg_attr(feature="swagger-ui", schema(example = 250))]
+    pub blackout: u32,
+    /// extra points for a player's first win of a round if it took at most
+    /// `speed_picks` picks
+    #[cfg_attr(feature="swagger-ui", schema(example = 25))]
+    pub speed_bonus: u32,
+    /// how many picks a win can take at most to get the `speed_bonus`, 0
+    /// turns it off
+    #[cfg_attr(feature="swagger-ui", schema(example = 10))]
+    pub speed_picks: usize,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            first_line: 100,
+            line: 0,
+            blackout: 250,
+            speed_bonus: 25,
+            speed_picks: 10,
+        }
+    }
+}
+
+impl Scoring {
+    /// whether rounds with `patterns` go on after their first win, which they
+    /// do if later lines score or a blackout can still be won after a line
+    pub fn keeps_round_running(&self, patterns: &[WinPattern]) -> bool {
+        let later_blackout = self.blackout > 0
+            && patterns.contains(&WinPattern::Blackout)
+            && patterns.iter().any(|p| *p != WinPattern::Blackout);
+        self.line > 0 || later_blackout
+    }
+
+    /// how many points every player got in a round won by `winners`
+    pub fn round_scores(&self, winners: &[Winner]) -> HashMap<Ulid, u32> {
+        let first_line = winners.iter()
+            .filter(|w| w.win.pattern != WinPattern::Blackout)
+            .map(|w| w.picks)
+            .min();
+        let mut scores = HashMap::new();
+        // players who already got the points for the first line or the speed
+        // bonus, which are only given once per round
+        let mut first = HashSet::new();
+        let mut fast = HashSet::new();
+        for winner in winners {
+            let mut points = match winner.win.pattern {
+                WinPattern::Blackout => self.blackout,
+                _ if Some(winner.picks) == first_line && first.insert(winner.player) => self.first_line,
+                _ => self.line,
+            };
+            if winner.picks <= self.speed_picks && fast.insert(winner.player) {
+                points += self.speed_bonus;
+            }
+            *scores.entry(winner.player).or_default() += points;
+        }
+        scores
+    }
+}
+
+/// how a player's score changed after a win or a revoked one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreChange {
+    pub player: Ulid,
+    /// how many points were added, negative if some were taken away
+    pub delta: i64,
+    /// the player's score across every round after the change
+    pub score: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct LeaderboardEntry {
+    /// players with the same score share a rank
+    #[cfg_attr(feature="swagger-ui", schema(example = 1))]
+    rank: u32,
+    player: Ulid,
+    /// the player's twitch display name, if it is known
+    #[cfg_attr(feature="swagger-ui", schema(example = "xQc"))]
+    display_name: Option<String>,
+    #[cfg_attr(feature="swagger-ui", schema(example = 225))]
+    score: u32,
+}
+
+/// get the scores of everyone who played in a game, across all its rounds
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    get,
+    path = "/game/{id}/leaderboard",
+    tag = "Game",
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game"),
+        AccessKey
+    ),
+    responses(
+        (status = 200, description = "Every player, from the highest score to the lowest", body = [LeaderboardEntry]),
+        (status = 403, description = "The game is private and the key was missing or wrong", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError)
+    )
+))]
+pub async fn get_leaderboard(
+    claims: Option<Claims>,
+    id: Path<Ulid>,
+    key: AccessKey,
+    games_manager: Data<GamesManager>
+) -> Result<Json<Vec<LeaderboardEntry>>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.check_access(claims.as_ref(), key.0.as_deref())?;
+
+    let scores = game.get_scores();
+    let ids: Vec<Ulid> = scores.iter().map(|(id, _)| *id).collect();
+    let mut names = games_manager.display_names(&ids).await;
+
+    let mut rank = 0;
+    let mut last_score = None;
+    let leaderboard = scores.into_iter().enumerate().map(|(i, (player, score))| {
+        if last_score != Some(score) {
+            rank = i as u32 + 1;
+            last_score = Some(score);
+        }
+        LeaderboardEntry {
+            rank,
+            player,
+            display_name: names.remove(&player),
+            score,
+        }
+    }).collect();
+
+    return Ok(Json(leaderboard));
+}
@@ -0,0 +1,26 @@
+use std::sync::{atomic::Ordering, Arc};
+
+use super::Game;
+
+/// a spot among the spectators of a game, which is given back when this is
+/// dropped
+#[derive(Debug)]
+pub struct Spectator {
+    game: Arc<Game>,
+}
+
+impl Spectator {
+    pub(super) fn new(game: Arc<Game>) -> Self {
+        Self { game }
+    }
+
+    pub fn game(&self) -> &Arc<Game> {
+        &self.game
+    }
+}
+
+impl Drop for Spectator {
+    fn drop(&mut self) {
+        self.game.spectators.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,321 @@
+use chrono::{DateTime, Utc};
+use hashbrown::HashMap;
+use log::error;
+use sqlx::{postgres::{PgHasArrayType, PgTypeInfo}, prelude::FromRow, types::Json, PgPool};
+use ulid::Ulid;
+use uuid::Uuid;
+
+use super::{access::{Access, Visibility}, info::GameInfo, lifecycle::GameState, options::GameOptions, playerdata::PlayerData, role::Role, round::Round, suggest::Suggestion, win::Winner, Game, Item};
+
+/// an [Item] as stored in the `bingo_item` composite type
+#[derive(Debug, Clone, sqlx::Type)]
+#[sqlx(type_name = "bingo_item")]
+struct DbItem {
+    inner_text: String,
+    picked: bool,
+}
+
+impl PgHasArrayType for DbItem {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_bingo_item")
+    }
+}
+
+impl From<&Item> for DbItem {
+    fn from(value: &Item) -> Self {
+        Self {
+            inner_text: value.text.to_string(),
+            picked: value.picked,
+        }
+    }
+}
+
+impl From<DbItem> for Item {
+    fn from(value: DbItem) -> Self {
+        Self {
+            text: value.inner_text.into(),
+            picked: value.picked,
+        }
+    }
+}
+
+/// a row of the `games` table
+#[derive(Debug, Clone, FromRow)]
+struct GameRecord {
+    game_id: Uuid,
+    creator_id: Option<Uuid>,
+    roles: Json<HashMap<Ulid, Role>>,
+    visibility: String,
+    invite_code: Option<String>,
+    password_hash: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    cover_url: Option<String>,
+    language: Option<String>,
+    tags: Vec<String>,
+    creation_date: DateTime<Utc>,
+    board_size: i32,
+    items: Vec<DbItem>,
+    options: Json<GameOptions>,
+    state: String,
+    picks: Vec<i32>,
+    winners: Json<Vec<Winner>>,
+    rounds: Json<Vec<Round>>,
+    suggestions: Json<Vec<Suggestion>>,
+}
+
+impl GameRecord {
+    fn new(game: &Game) -> Self {
+        let info = game.get_info();
+        let access = game.get_access();
+        Self {
+            game_id: game.id.into(),
+            creator_id: game.creator.map(Uuid::from),
+            roles: Json(game.get_roles().clone()),
+            visibility: access.visibility.as_str().to_owned(),
+            invite_code: access.invite,
+            password_hash: access.password_hash,
+            title: info.title,
+            description: info.description,
+            cover_url: info.cover_url,
+            language: info.language,
+            tags: info.tags,
+            creation_date: game.id.datetime().into(),
+            board_size: game.get_size() as i32,
+            items: game.get_items().iter().map(DbItem::from).collect(),
+            options: Json(GameOptions::clone(&game.get_options())),
+            state: game.get_state().as_str().to_owned(),
+            picks: game.get_picks().iter().map(|i| *i as i32).collect(),
+            winners: Json(game.get_winners().clone()),
+            rounds: Json(game.get_rounds().clone()),
+            suggestions: Json(game.get_suggestions().clone()),
+        }
+    }
+}
+
+/// a row of the `players` table, joined with the user's ULID
+///
+/// players waiting in the lobby have neither a seed nor items
+#[derive(Debug, Clone, FromRow)]
+struct PlayerRecord {
+    user_id: Uuid,
+    seed: Option<i64>,
+    items: Option<Vec<Option<i32>>>,
+    marks: Option<Vec<bool>>,
+}
+
+/// saves games to and loads them from the database, so they survive restarts
+#[derive(Debug, Clone)]
+pub struct GameStore {
+    pool: PgPool,
+}
+
+impl GameStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// performs an UPSERT of everything about `game` except its players
+    pub async fn save_game(&self, game: &Game) -> Result<(), sqlx::Error> {
+        let _guard = game.save_lock.lock().await;
+        // the record is taken only after getting the lock so an older state
+        // can never overwrite a newer one
+        let record = GameRecord::new(game);
+        sqlx::query("INSERT INTO games (game_id, creation_date, board_size, items, options, state, picks, winners, rounds, suggestions, title, description, cover_url, language, tags, creator_id, roles, visibility, invite_code, password_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+            ON CONFLICT (game_id) DO UPDATE
+            SET board_size = $3,
+            items = $4,
+            options = $5,
+            state = $6,
+            picks = $7,
+            winners = $8,
+            rounds = $9,
+            suggestions = $10,
+            title = $11,
+            description = $12,
+            cover_url = $13,
+            language = $14,
+            tags = $15,
+            roles = $17,
+            visibility = $18,
+            invite_code = $19,
+            password_hash = $20;"
+        ).bind(record.game_id)
+            .bind(record.creation_date)
+            .bind(record.board_size)
+            .bind(record.items)
+            .bind(record.options)
+            .bind(record.state)
+            .bind(record.picks)
+            .bind(record.winners)
+            .bind(record.rounds)
+            .bind(record.suggestions)
+            .bind(record.title)
+            .bind(record.description)
+            .bind(record.cover_url)
+            .bind(record.language)
+            .bind(record.tags)
+            .bind(record.creator_id)
+            .bind(record.roles)
+            .bind(record.visibility)
+            .bind(record.invite_code)
+            .bind(record.password_hash)
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// performs an UPSERT of the boards of the players with the user IDs
+    /// `user_ids`, along with the game itself for changes that touched both
+    pub async fn save_players(&self, game: &Game, user_ids: &[Ulid]) -> Result<(), sqlx::Error> {
+        self.save_game(game).await?;
+        for user_id in user_ids {
+            self.upsert_player(game, *user_id).await?;
+        }
+        Ok(())
+    }
+
+    /// performs an UPSERT of the board of the player with the user ID
+    /// `user_id`, the game is only saved as well if it doesn't have a row yet
+    /// for the player's row to reference
+    pub async fn save_player(&self, game: &Game, user_id: Ulid) -> Result<(), sqlx::Error> {
+        if !self.upsert_player(game, user_id).await? {
+            self.save_game(game).await?;
+            self.upsert_player(game, user_id).await?;
+        }
+        Ok(())
+    }
+
+    /// writes the row of the player with the user ID `user_id`
+    ///
+    /// returns false if nothing was written because the game or the user
+    /// don't have a row to reference
+    async fn upsert_player(&self, game: &Game, user_id: Ulid) -> Result<bool, sqlx::Error> {
+        let player = game.get_player(user_id);
+        if player.is_none() && !game.is_in_lobby(user_id) {
+            return Ok(true);
+        }
+        let items: Option<Vec<Option<i32>>> = player.as_ref()
+            .map(|p| p.board().iter().map(|i| i.map(|i| i as i32)).collect());
+        let marks: Option<Vec<bool>> = player.as_ref().map(|p| p.marks().to_vec());
+        let result = sqlx::query("INSERT INTO players (user_id, game_id, items, seed, marks)
+            SELECT users.id, games.id, $3, $4, $5
+            FROM users, games
+            WHERE users.user_id = $1 AND games.game_id = $2
+            ON CONFLICT (user_id, game_id) DO UPDATE
+            SET items = $3,
+            seed = $4,
+            marks = $5;"
+        ).bind(Uuid::from(user_id))
+            .bind(Uuid::from(game.id))
+            .bind(items)
+            // bigint is signed, this is undone when loading
+            .bind(player.map(|p| p.seed() as i64))
+            .bind(marks)
+            .execute(&self.pool).await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// the twitch display names of the users with the ULIDs `ids`, users that
+    /// aren't found are left out
+    pub async fn display_names(&self, ids: &[Ulid]) -> Result<HashMap<Ulid, String>, sqlx::Error> {
+        let ids: Vec<Uuid> = ids.iter().copied().map(Uuid::from).collect();
+        let names = sqlx::query_as::<_, (Uuid, String)>("SELECT user_id, twitch_display_name
+            FROM users WHERE
+            user_id = ANY($1);"
+        ).bind(ids)
+            .fetch_all(&self.pool).await?;
+        Ok(names.into_iter().map(|(id, name)| (Ulid::from(id), name)).collect())
+    }
+
+    /// the ULID of the user with the twitch login `login`, if they ever
+    /// logged in
+    pub async fn find_user(&self, login: &str) -> Result<Option<Ulid>, sqlx::Error> {
+        let id = sqlx::query_scalar::<_, Uuid>("SELECT user_id
+            FROM users WHERE
+            twitch_login = $1;"
+        ).bind(login.to_lowercase())
+            .fetch_optional(&self.pool).await?;
+        Ok(id.map(Ulid::from))
+    }
+
+    /// marks a game as no longer active, so it isn't loaded again on startup
+    pub async fn deactivate(&self, id: Ulid) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE games SET active = false WHERE game_id = $1;")
+            .bind(Uuid::from(id))
+            .execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// loads every active game along with its players, games whose
+    /// visibility or state can't be read are logged and left out
+    pub async fn load_active(&self) -> Result<Vec<Game>, sqlx::Error> {
+        let records = sqlx::query_as::<_, GameRecord>("SELECT
+            game_id, creator_id, roles, visibility, invite_code, password_hash, title, description, cover_url, language, tags, creation_date, board_size, items, options, state, picks, winners, rounds, suggestions
+            FROM games WHERE
+            active = true;"
+        ).fetch_all(&self.pool).await?;
+
+        let mut games = Vec::with_capacity(records.len());
+        for record in records {
+            let (Ok(visibility), Ok(state)) = (record.visibility.parse::<Visibility>(), record.state.parse::<GameState>()) else {
+                error!(
+                    "not loading game {}, its visibility {:?} or state {:?} is unknown",
+                    Ulid::from(record.game_id), record.visibility, record.state
+                );
+                continue;
+            };
+            let player_records = sqlx::query_as::<_, PlayerRecord>("SELECT
+                users.user_id, players.seed, players.items, players.marks
+                FROM players
+                INNER JOIN users ON players.user_id = users.id
+                INNER JOIN games ON players.game_id = games.id
+                WHERE games.game_id = $1
+                ORDER BY players.id;"
+            ).bind(record.game_id)
+                .fetch_all(&self.pool).await?;
+
+            let mut lobby = Vec::new();
+            let mut players = HashMap::new();
+            for p in player_records {
+                match (p.seed, p.items) {
+                    (Some(seed), Some(items)) => {
+                        let board = items.into_iter().map(|i| i.map(|i| i as usize)).collect();
+                        let marks = p.marks.map(Vec::into_boxed_slice);
+                        players.insert(Ulid::from(p.user_id), PlayerData::from_parts(seed as u64, board, marks));
+                    },
+                    _ => lobby.push(Ulid::from(p.user_id)),
+                }
+            }
+
+            games.push(Game::restore(
+                record.game_id.into(),
+                record.creator_id.map(Ulid::from),
+                record.roles.0,
+                Access {
+                    visibility,
+                    invite: record.invite_code,
+                    password_hash: record.password_hash,
+                },
+                GameInfo {
+                    title: record.title,
+                    description: record.description,
+                    cover_url: record.cover_url,
+                    language: record.language,
+                    tags: record.tags,
+                },
+                record.board_size as u32,
+                record.items.into_iter().map(Item::from).collect(),
+                record.options.0,
+                state,
+                record.picks.into_iter().map(|i| i as usize).collect(),
+                lobby,
+                players,
+                record.winners.0,
+                record.rounds.0,
+                record.suggestions.0
+            ));
+        }
+        Ok(games)
+    }
+}
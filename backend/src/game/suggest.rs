@@ -0,0 +1,151 @@
+use actix_web::{web::{Data, Json, Path}, HttpResponse};
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager, role::Permission};
+
+/// an item a player would like to see on the boards, it's only added to the
+/// game once the host approves it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Suggestion {
+    #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
+    pub id: Ulid,
+    /// the user ID of the player who suggested it
+    #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
+    pub player: Ulid,
+    #[cfg_attr(feature="swagger-ui", schema(example = "streamer says \"chat\""))]
+    pub text: String,
+    pub status: SuggestionStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum SuggestionStatus {
+    /// waiting for the host to review it
+    Pending,
+    /// the host added it to the game's items
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct SuggestRequest {
+    #[cfg_attr(feature="swagger-ui", schema(example = "streamer says \"chat\""))]
+    pub(super) text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct ReviewRequest {
+    /// the ID of the suggestion
+    #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
+    pub(super) suggestion: Ulid,
+    /// whether the suggestion is added to the game's items
+    pub(super) approved: bool,
+}
+
+/// suggest an item for the game while it's in the lobby
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/suggestions",
+    tag = "Game",
+    security(
+        ("user_token" = [])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = SuggestRequest,
+    responses(
+        (status = 200, description = "The suggestion is waiting for the host to review it", body = Suggestion),
+        (status = 400, description = "The suggestion was empty or too long", body = GameError),
+        (status = 401, description = "The request wasn't authenticated", body = GameError),
+        (status = 404, description = "No game with that ULID was found or the user hasn't joined it", body = GameError),
+        (status = 409, description = "The item was already suggested, the player has no suggestions left or the game left the lobby", body = GameError)
+    )
+))]
+pub async fn suggest_item(
+    claims: Claims,
+    id: Path<Ulid>,
+    suggestion: Json<SuggestRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<Suggestion>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+
+    let suggestion = game.suggest_item(claims.user_id(), &suggestion.text)?;
+    games_manager.save_game(&game);
+
+    return Ok(Json(suggestion));
+}
+
+/// get the suggestions that are waiting to be reviewed, oldest first
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    get,
+    path = "/game/{id}/suggestions",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    responses(
+        (status = 200, description = "The moderation queue of the game", body = [Suggestion]),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError)
+    )
+))]
+pub async fn get_suggestions(
+    claims: Claims,
+    id: Path<Ulid>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<Vec<Suggestion>>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::RunBoard)?;
+
+    let pending = game.get_suggestions().iter()
+        .filter(|s| s.status == SuggestionStatus::Pending)
+        .cloned()
+        .collect();
+
+    return Ok(Json(pending));
+}
+
+/// approve or reject a suggestion, approved ones are added to the game's items
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/suggestions/review",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = ReviewRequest,
+    responses(
+        (status = 204, description = "The suggestion was reviewed and all players were notified"),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID or no pending suggestion with that ID was found", body = GameError),
+        (status = 409, description = "The game left the lobby or the item is already in it", body = GameError)
+    )
+))]
+pub async fn review_suggestion(
+    claims: Claims,
+    id: Path<Ulid>,
+    review: Json<ReviewRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::RunBoard)?;
+
+    game.review_suggestion(review.suggestion, review.approved)?;
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
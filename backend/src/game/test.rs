@@ -1,9 +1,37 @@
-use actix_web::{http::StatusCode, middleware::Logger, test::{self, TestRequest}, web::{resource, Data}, App};
+#![allow(clippy::unwrap_used)]
+
+use std::{sync::Arc, time::Duration};
+
+use actix_web::{cookie::Cookie, http::StatusCode, middleware::Logger, test::{self, TestRequest}, web::{resource, Data}, App, ResponseError};
+use base64::Engine;
 use env_logger::Env;
+use serde_json::json;
+use ulid::Ulid;
+
+use crate::{auth::jwt::{create_new_jwt, Claims, UserKind}, event::ServerEvent, game::create::CreatedGame};
+
+use super::{access::{get_invite, resolve_invite, rotate_invite, update_access, Visibility, ACCESS_KEY_HEADER, INVITE_LENGTH}, board::get_board, code::{Codes, ALPHABET, CODE_LENGTH}, claim::claim_bingo, create::{create_game, CreateGameRequest}, error::GameError, info::GameInfo, join::join_game, lifecycle::{start_game, GameState}, mark::{mark_cell, MarkRequest}, get::get_game, manager::{Eviction, Expiry, GamesManager}, options::{GameOptions, WinMode}, score::{get_leaderboard, Scoring}, update::update_game, suggest::{review_suggestion, suggest_item, ReviewRequest, SuggestRequest, Suggestion, SuggestionStatus}, vote::Voting, pick::{pick_item, PickRequest}, playerdata::PlayerData, role::{get_roles, Permission, Role}, win::{find_line, find_win, Line, RevokeReason, WinPattern}, Game, Item};
 
-use crate::game::create::CreatedGame;
+fn auth_cookie(user_id: Ulid) -> Cookie<'static> {
+    std::env::set_var("JWT_SECRET", base64::prelude::BASE64_STANDARD.encode("bingo test secret"));
+    let jwt = create_new_jwt(Claims::new(user_id, UserKind::Player, Duration::from_secs(60)));
+    Cookie::new("jwt", jwt)
+}
 
-use super::{create::{create_game, CreateGameRequest}, get::get_game, manager::GamesManager};
+/// a game of size 5 with the items `0` to `24` and the default options
+fn test_game() -> Game {
+    test_game_with(25, Default::default())
+}
+
+/// a game of size 5 with `items` items, each named after its index
+fn test_game_with(items: usize, options: GameOptions) -> Game {
+    Game::new(Ulid::new(), 5, (0..items).map(|i| i.to_string().into()).collect(), options)
+}
+
+/// a request creating a public game of size 5 out of `items`
+fn create_request(items: Vec<String>) -> CreateGameRequest {
+    CreateGameRequest { info: Default::default(), items, size: 5, options: Default::default(), visibility: Default::default(), password: None }
+}
 
 
 #[actix_web::test]
@@ -12,7 +40,7 @@ async fn test_create() {
         Env::new().default_filter_or("DEBUG")
     );
 
-    let mut app = test::init_service(
+    let app = test::init_service(
         App::new()
             .wrap(Logger::default())
             .app_data(Data::new(GamesManager::new()))
@@ -20,19 +48,1168 @@ async fn test_create() {
             .service(resource("/get").get(get_game))
     ).await;
 
-    let items = vec![String::from("bleh"); 25];
+    let mut items = vec![String::from("bleh"); 25];
+    let cookie = auth_cookie(Ulid::new());
+
+    let anonymous_req = TestRequest::post()
+        .uri("/create")
+        .set_json(create_request(items.clone()));
+    let resp = test::call_service(&app, anonymous_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let duplicate_req = TestRequest::post()
+        .uri("/create")
+        .cookie(cookie.clone())
+        .set_json(create_request(items.clone()));
+    let resp = test::call_service(&app, duplicate_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let error: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(error["error"], "duplicate_item");
+
+    for (i, item) in items.iter_mut().enumerate() {
+        item.push_str(&i.to_string());
+    }
 
     let create_req = TestRequest::post()
         .uri("/create")
-        .set_json(CreateGameRequest{ items, size: 5 });
+        .cookie(cookie)
+        .set_json(create_request(items));
 
-    let create_resp: CreatedGame = test::call_and_read_body_json(&mut app, create_req.to_request()).await;
+    let create_resp: CreatedGame = test::call_and_read_body_json(&app, create_req.to_request()).await;
 
     let get_req = TestRequest::get()
         .uri(&format!("/get?id={}", create_resp.id))
         .param("id", create_resp.id.to_string());
 
-    let get_resp = test::call_service(&mut app, get_req.to_request()).await;
+    let get_resp = test::call_service(&app, get_req.to_request()).await;
 
     assert!(get_resp.status() == StatusCode::OK, "status code: {}, body: {:?}", get_resp.status(), get_resp.map_into_boxed_body())
 }
+
+#[actix_web::test]
+async fn test_pick() {
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(GamesManager::new()))
+            .service(resource("/create").post(create_game))
+            .service(resource("/get").get(get_game))
+            .service(resource("/{id}/pick").post(pick_item))
+            .service(resource("/{id}/start").post(start_game))
+    ).await;
+
+    let items = (0..25).map(|i| i.to_string()).collect();
+    let cookie = auth_cookie(Ulid::new());
+
+    let create_req = TestRequest::post()
+        .uri("/create")
+        .cookie(cookie.clone())
+        .set_json(create_request(items));
+
+    let created: CreatedGame = test::call_and_read_body_json(&app, create_req.to_request()).await;
+
+    let anonymous_req = TestRequest::post()
+        .uri(&format!("/{}/pick", created.id))
+        .set_json(PickRequest { idx: 3 });
+    let resp = test::call_service(&app, anonymous_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let player_req = TestRequest::post()
+        .uri(&format!("/{}/pick", created.id))
+        .cookie(auth_cookie(Ulid::new()))
+        .set_json(PickRequest { idx: 3 });
+    let resp = test::call_service(&app, player_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN, "only the host can pick");
+
+    let pick_req = TestRequest::post()
+        .uri(&format!("/{}/pick", created.id))
+        .cookie(cookie.clone())
+        .set_json(PickRequest { idx: 3 });
+    let resp = test::call_service(&app, pick_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT, "nothing can be picked in the lobby");
+
+    let start_req = TestRequest::post().uri(&format!("/{}/start", created.id)).cookie(cookie.clone());
+    let resp = test::call_service(&app, start_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    for (idx, status) in [(3, StatusCode::NO_CONTENT), (3, StatusCode::CONFLICT), (25, StatusCode::BAD_REQUEST), (7, StatusCode::NO_CONTENT)] {
+        let pick_req = TestRequest::post()
+            .uri(&format!("/{}/pick", created.id))
+            .cookie(cookie.clone())
+            .set_json(PickRequest { idx });
+        let resp = test::call_service(&app, pick_req.to_request()).await;
+        assert_eq!(resp.status(), status, "picking item {idx}");
+    }
+
+    let get_req = TestRequest::get().uri(&format!("/get?id={}", created.id));
+    let game: serde_json::Value = test::call_and_read_body_json(&app, get_req.to_request()).await;
+
+    assert_eq!(game["state"], "running");
+    assert_eq!(game["picks"], serde_json::json!([3, 7]));
+    assert_eq!(game["items"][3]["picked"], true);
+    assert_eq!(game["items"][4]["picked"], false);
+}
+
+#[test]
+fn test_lines() {
+    for size in (5..=23).step_by(2) {
+        let lines: Vec<Line> = Line::all(size).collect();
+        assert_eq!(lines.len(), size as usize * 2 + 2);
+
+        let mut items: Vec<Item> = (0..size.pow(2)).map(|i| i.to_string().into()).collect();
+        let board: Vec<Option<usize>> = (0..items.len()).map(Some).collect();
+
+        for line in lines {
+            items.iter_mut().for_each(|i| i.picked = false);
+            for cell in line.cells(size) {
+                assert_eq!(find_line(&board, size, &items), None, "{line:?} on size {size}");
+                items[cell].picked = true;
+            }
+            assert_eq!(find_line(&board, size, &items), Some(line), "{line:?} on size {size}");
+        }
+    }
+}
+
+#[test]
+fn test_winner() {
+    let game = test_game();
+
+    let player = Ulid::new();
+    game.add_new_player(player).unwrap();
+    assert_eq!(game.start(), Ok(vec![player]));
+    let mut rx = game.subscribe_to();
+    let column: Vec<usize> = {
+        let players = game.players.read();
+        let board = players[&player].board();
+        Line::Column(2).cells(5).filter_map(|cell| board[cell]).collect()
+    };
+
+    for idx in column {
+        assert!(!game.is_over());
+        game.pick_item(idx).unwrap();
+    }
+    assert_eq!(game.get_state(), GameState::Finished);
+    assert_eq!(game.pick_item(24), Err(GameError::GameOver));
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event.event);
+    }
+    assert!(matches!(events.last(), Some(ServerEvent::GameOver)));
+    assert!(matches!(events[events.len() - 2], ServerEvent::StateChanged { state: GameState::Finished }));
+    assert!(events.iter().any(|e| matches!(e, ServerEvent::Winner { player: p, .. } if *p == player)));
+}
+
+#[test]
+fn test_patterns() {
+    let size = 7;
+    let board: Vec<Option<usize>> = (0..size * size).map(Some).collect();
+    let custom = WinPattern::Custom {
+        name: None,
+        rows: vec!["1000000".into(), "0000000".into(), "0000000".into(), "0001000".into(), "0000000".into(), "0000000".into(), "0000001".into()]
+    };
+    assert!(custom.fits(size as u32));
+    assert!(!custom.fits(5));
+    assert!(!WinPattern::Custom { name: None, rows: vec!["0000000".into(); 7] }.fits(7));
+
+    let patterns = [
+        (WinPattern::FourCorners, vec![0, 6, 42, 48]),
+        (WinPattern::X, (0..7).flat_map(|i| [i * 7 + i, i * 7 + 6 - i]).collect()),
+        (WinPattern::Plus, (0..7).flat_map(|i| [3 * 7 + i, i * 7 + 3]).collect()),
+        (WinPattern::PictureFrame, (0..7).flat_map(|i| [i, 42 + i, i * 7, i * 7 + 6]).collect()),
+        (WinPattern::Blackout, (0..49).collect()),
+        (custom, vec![0, 24, 48]),
+    ];
+
+    for (pattern, mut cells) in patterns {
+        cells.sort();
+        cells.dedup();
+        let mut items: Vec<Item> = (0..size * size).map(|i| i.to_string().into()).collect();
+        for cell in &cells {
+            assert_eq!(find_win(&board, size as u32, &items, std::slice::from_ref(&pattern)), None, "{pattern:?}");
+            items[*cell].picked = true;
+        }
+        let win = find_win(&board, size as u32, &items, &[WinPattern::Blackout, pattern.clone()]);
+        assert_eq!(win.map(|w| w.pattern), Some(pattern), "the first pattern in the list that matches should win");
+    }
+}
+
+#[test]
+fn test_free_space() {
+    let size = 5;
+    let mut board: Vec<Option<usize>> = (0..24).map(Some).collect();
+    board.insert(12, None);
+    let mut items: Vec<Item> = (0..24).map(|i| i.to_string().into()).collect();
+
+    // the middle row only has 4 items in it
+    for idx in [10, 11, 12, 13] {
+        assert_eq!(find_line(&board, size, &items), None);
+        items[idx].picked = true;
+    }
+    assert_eq!(find_line(&board, size, &items), Some(Line::Row(2)));
+
+    let game = test_game_with(24, GameOptions {
+        free_space: true,
+        ..Default::default()
+    });
+    let player = Ulid::new();
+    game.add_new_player(player).unwrap();
+    game.start().unwrap();
+    let players = game.players.read();
+    let board = players[&player].board();
+    assert_eq!(board[12], None);
+    assert!(board.iter().enumerate().all(|(cell, idx)| cell == 12 || idx.is_some_and(|i| i < 24)));
+}
+
+#[test]
+fn test_seeded_boards() {
+    for size in (5..=23).step_by(2) {
+        for free_space in [false, true] {
+            let items_len = size as usize * size as usize;
+            let player = PlayerData::new_random(size, items_len, free_space);
+            let mut items: Vec<usize> = player.items().collect();
+            items.sort();
+            items.dedup();
+            assert_eq!(items.len(), items_len - free_space as usize, "boards can't repeat items");
+
+            let regenerated = PlayerData::from_seed(player.seed(), size, items_len, free_space);
+            assert_eq!(player.board(), regenerated.board());
+        }
+    }
+
+    // with exactly as many items as two boards need, diverse boards shouldn't
+    // overlap much
+    let game = test_game_with(50, GameOptions {
+        diverse_boards: true,
+        ..Default::default()
+    });
+    let (first, second) = (Ulid::new(), Ulid::new());
+    game.add_new_player(first).unwrap();
+    game.add_new_player(second).unwrap();
+    game.start().unwrap();
+    let players = game.players.read();
+    let overlap = players[&first].items().filter(|i| players[&second].board().contains(&Some(*i))).count();
+    assert!(overlap < 15, "boards overlapped in {overlap} items");
+}
+
+#[actix_web::test]
+async fn test_join() {
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(GamesManager::new()))
+            .service(resource("/create").post(create_game))
+            .service(resource("/{id}/join").post(join_game))
+            .service(resource("/{id}/board").get(get_board))
+            .service(resource("/{id}/start").post(start_game))
+    ).await;
+
+    let items = (0..30).map(|i| i.to_string()).collect();
+    let options = GameOptions { free_space: true, free_space_label: Some("juh".into()), ..Default::default() };
+    // the host plays along
+    let cookie = auth_cookie(Ulid::new());
+    let create_req = TestRequest::post()
+        .uri("/create")
+        .cookie(cookie.clone())
+        .set_json(CreateGameRequest { options, ..create_request(items) });
+    let created: CreatedGame = test::call_and_read_body_json(&app, create_req.to_request()).await;
+
+    let board_req = TestRequest::get().uri(&format!("/{}/board", created.id)).cookie(cookie.clone());
+    let resp = test::call_service(&app, board_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND, "players shouldn't have a board before joining");
+
+    let anonymous_req = TestRequest::post().uri(&format!("/{}/join", created.id));
+    let resp = test::call_service(&app, anonymous_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let join_req = TestRequest::post().uri(&format!("/{}/join", created.id)).cookie(cookie.clone());
+    let resp = test::call_service(&app, join_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED, "players in the lobby should wait for their board");
+
+    let board_req = TestRequest::get().uri(&format!("/{}/board", created.id)).cookie(cookie.clone());
+    let resp = test::call_service(&app, board_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let start_req = TestRequest::post().uri(&format!("/{}/start", created.id)).cookie(cookie.clone());
+    let resp = test::call_service(&app, start_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let join_req = TestRequest::post().uri(&format!("/{}/join", created.id)).cookie(cookie.clone());
+    let joined: serde_json::Value = test::call_and_read_body_json(&app, join_req.to_request()).await;
+    assert_eq!(joined["cells"].as_array().map(|c| c.len()), Some(25));
+    assert_eq!(joined["cells"][12], serde_json::json!({ "text": "juh", "marked": true }));
+
+    let rejoin_req = TestRequest::post().uri(&format!("/{}/join", created.id)).cookie(cookie.clone());
+    let rejoined: serde_json::Value = test::call_and_read_body_json(&app, rejoin_req.to_request()).await;
+    assert_eq!(joined, rejoined, "joining again should return the same board");
+
+    let board_req = TestRequest::get().uri(&format!("/{}/board", created.id)).cookie(cookie);
+    let board: serde_json::Value = test::call_and_read_body_json(&app, board_req.to_request()).await;
+    assert_eq!(joined, board);
+
+    let other_req = TestRequest::post().uri(&format!("/{}/join", created.id)).cookie(auth_cookie(Ulid::new()));
+    let other: serde_json::Value = test::call_and_read_body_json(&app, other_req.to_request()).await;
+    assert_ne!(joined["seed"], other["seed"]);
+}
+
+#[test]
+fn test_lifecycle() {
+    let game = test_game();
+    let mut rx = game.subscribe_to();
+
+    assert_eq!(game.get_state(), GameState::Lobby);
+    assert_eq!(game.pause(), Err(GameError::InvalidTransition { from: GameState::Lobby, to: GameState::Paused }));
+    assert_eq!(game.resume(), Err(GameError::InvalidTransition { from: GameState::Lobby, to: GameState::Running }));
+    game.start().unwrap();
+    assert!(game.start().is_err());
+    game.pause().unwrap();
+    assert_eq!(game.pick_item(0), Err(GameError::NotRunning));
+    game.resume().unwrap();
+    game.pick_item(0).unwrap();
+    assert!(game.archive().is_err());
+    game.end().unwrap();
+    assert_eq!(game.add_new_player(Ulid::new()), Err(GameError::GameOver));
+    game.archive().unwrap();
+    assert!(game.end().is_err());
+
+    let mut states = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let ServerEvent::StateChanged { state } = event.event {
+            states.push(state);
+        }
+    }
+    assert_eq!(states, [GameState::Running, GameState::Paused, GameState::Running, GameState::Finished, GameState::Archived]);
+}
+
+#[tokio::test]
+async fn test_expiry() {
+    let manager = GamesManager::new();
+    let idle = manager.new_game(test_game()).get_id();
+    let watched = manager.new_game(test_game());
+    let mut rx = watched.subscribe_to();
+    let spectated = manager.new_game(test_game());
+    let _spectator_rx = spectated.subscribe_to();
+    let spectator = manager.spectate(&spectated).unwrap();
+
+    let expiry = Expiry { idle_timeout: Duration::ZERO, max_lifetime: Duration::from_secs(3600) };
+    let mut swept = manager.sweep(&expiry);
+    let mut expected = vec![(idle, Eviction::Idle), (spectated.get_id(), Eviction::Idle)];
+    swept.sort_by_key(|(id, _)| *id);
+    expected.sort_by_key(|(id, _)| *id);
+    assert_eq!(swept, expected);
+    assert!(manager.get_game(idle).is_none());
+    assert!(manager.get_game(spectated.get_id()).is_none(), "spectators alone shouldn't keep a game around");
+    drop(spectator);
+    assert!(manager.get_game(watched.get_id()).is_some(), "games with someone connected shouldn't be idle");
+
+    let expiry = Expiry { idle_timeout: Duration::from_secs(3600), max_lifetime: Duration::ZERO };
+    assert_eq!(manager.sweep(&expiry), [(watched.get_id(), Eviction::Expired)]);
+    assert!(manager.get_game(watched.get_id()).is_none());
+    assert_eq!(watched.get_state(), GameState::Archived);
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event.event);
+    }
+    assert!(events.iter().any(|e| matches!(e, ServerEvent::GameOver)));
+}
+
+#[actix_web::test]
+async fn test_claim() {
+    let options = GameOptions { win_mode: WinMode::Claim, ..Default::default() };
+    let manager = GamesManager::new();
+    let game = manager.new_game(test_game_with(25, options));
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(manager))
+            .service(resource("/{id}/claim").post(claim_bingo))
+    ).await;
+
+    let player = Ulid::new();
+    game.add_new_player(player).unwrap();
+    game.start().unwrap();
+    let mut rx = game.subscribe_to();
+    let row: Vec<usize> = {
+        let players = game.players.read();
+        let board = players[&player].board();
+        Line::Row(0).cells(5).filter_map(|cell| board[cell]).collect()
+    };
+
+    let claim_req = || TestRequest::post().uri(&format!("/{}/claim", game.get_id())).cookie(auth_cookie(player));
+    let resp = test::call_service(&app, claim_req().to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert!(matches!(rx.try_recv().map(|e| e.event), Ok(ServerEvent::ClaimRejected { player: p }) if p == player));
+
+    let resp = test::call_service(&app, claim_req().to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert!(rx.try_recv().is_err(), "a rejection is only announced once per pick");
+
+    game.pick_item(row[0]).unwrap();
+    assert_eq!(game.claim_bingo(player), Err(GameError::NoBingo));
+    let events: Vec<ServerEvent> = std::iter::from_fn(|| rx.try_recv().ok()).map(|e| e.event).collect();
+    assert!(matches!(events.as_slice(), [ServerEvent::NewBall { .. }, ServerEvent::ClaimRejected { .. }]));
+
+    let outsider = TestRequest::post().uri(&format!("/{}/claim", game.get_id())).cookie(auth_cookie(Ulid::new()));
+    let resp = test::call_service(&app, outsider.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    for idx in &row[1..] {
+        game.pick_item(*idx).unwrap();
+    }
+    assert_eq!(game.get_state(), GameState::Running, "wins shouldn't be found automatically");
+
+    let wins: serde_json::Value = test::call_and_read_body_json(&app, claim_req().to_request()).await;
+    assert_eq!(wins[0]["pattern"], "line");
+    assert_eq!(game.get_state(), GameState::Finished);
+    assert_eq!(game.claim_bingo(player), Err(GameError::GameOver));
+}
+
+#[actix_web::test]
+async fn test_marks() {
+    let options = GameOptions { strict_marks: true, free_space: true, ..Default::default() };
+    let manager = GamesManager::new();
+    let game = manager.new_game(test_game_with(24, options));
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(manager))
+            .service(resource("/{id}/mark").post(mark_cell))
+            .service(resource("/{id}/board").get(get_board))
+    ).await;
+
+    let player = Ulid::new();
+    let mark_req = |cell, marked| TestRequest::post()
+        .uri(&format!("/{}/mark", game.get_id()))
+        .cookie(auth_cookie(player))
+        .set_json(MarkRequest { cell, marked });
+
+    game.add_new_player(player).unwrap();
+    let resp = test::call_service(&app, mark_req(0, true).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT, "players in the lobby have nothing to mark");
+    game.start().unwrap();
+
+    let first = game.get_player(player).unwrap().board()[0].unwrap();
+    let resp = test::call_service(&app, mark_req(0, true).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT, "unpicked items can't be marked in strict games");
+    let resp = test::call_service(&app, mark_req(25, true).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    game.pick_item(first).unwrap();
+    let resp = test::call_service(&app, mark_req(0, true).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let resp = test::call_service(&app, mark_req(12, false).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let board_req = TestRequest::get().uri(&format!("/{}/board", game.get_id())).cookie(auth_cookie(player));
+    let board: serde_json::Value = test::call_and_read_body_json(&app, board_req.to_request()).await;
+    let marked: Vec<usize> = board["cells"].as_array().unwrap().iter().enumerate()
+        .filter(|(_, cell)| cell["marked"] == true)
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(marked, [0, 12], "the free space should stay marked");
+
+    let resp = test::call_service(&app, mark_req(0, false).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert!(!game.get_player(player).unwrap().marks()[0]);
+}
+
+#[test]
+fn test_corrections() {
+    let game = test_game_with(30, Default::default());
+    let player = Ulid::new();
+    game.add_new_player(player).unwrap();
+    assert_eq!(game.unpick_item(0), Err(GameError::NotRunning));
+    game.start().unwrap();
+
+    let board = game.get_player(player).unwrap();
+    let column: Vec<usize> = Line::Column(0).cells(5).filter_map(|cell| board.board()[cell]).collect();
+    let missing = (0..30).find(|idx| !board.board().contains(&Some(*idx))).unwrap();
+
+    assert_eq!(game.unpick_item(column[0]), Err(GameError::NotPicked));
+    for idx in &column[..4] {
+        game.pick_item(*idx).unwrap();
+    }
+    game.pick_item(missing).unwrap();
+    assert_eq!(game.replace_pick(missing, column[0]), Err(GameError::AlreadyPicked));
+    assert_eq!(game.replace_pick(column[4], missing), Err(GameError::NotPicked));
+
+    let mut rx = game.subscribe_to();
+    game.replace_pick(missing, column[4]).unwrap();
+    assert_eq!(game.get_state(), GameState::Finished);
+    assert_eq!(game.get_winners().len(), 1);
+    assert_eq!(*game.get_picks(), [column[0], column[1], column[2], column[3], column[4]]);
+
+    game.unpick_item(column[2]).unwrap();
+    assert_eq!(game.get_state(), GameState::Running, "the game should go on once its only win is revoked");
+    assert!(game.get_winners().is_empty());
+    assert!(!game.get_items()[column[2]].picked);
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event.event);
+    }
+    assert!(matches!(events[0], ServerEvent::Unpicked { idx } if idx == missing));
+    assert!(matches!(events[1], ServerEvent::NewBall { idx } if idx == column[4]));
+    assert!(matches!(
+        &events[events.len() - 4..],
+        [
+            ServerEvent::Unpicked { .. },
+            ServerEvent::WinRevoked { player: p, reason: RevokeReason::PickUndone { idx }, .. },
+            ServerEvent::ScoresChanged { changes },
+            ServerEvent::StateChanged { state: GameState::Running },
+        ] if *p == player && *idx == column[2] && changes[0].delta < 0 && changes[0].score == 0
+    ));
+
+    game.end().unwrap();
+    assert_eq!(game.unpick_item(column[0]), Err(GameError::GameOver), "games ended by the host can't be corrected");
+}
+
+#[test]
+fn test_rounds() {
+    let play_round = |game: &Game, player: Ulid| {
+        let board = game.get_player(player).unwrap();
+        for idx in Line::Row(1).cells(5).filter_map(|cell| board.board()[cell]) {
+            game.pick_item(idx).unwrap();
+        }
+        assert_eq!(game.get_state(), GameState::Finished);
+        board
+    };
+
+    for keep_boards in [false, true] {
+        let options = GameOptions { keep_boards, ..Default::default() };
+        let game = test_game_with(60, options);
+        let player = Ulid::new();
+        game.add_new_player(player).unwrap();
+        game.start().unwrap();
+        assert!(game.new_round().is_err(), "rounds can only be started once the current one is finished");
+
+        let first_board = play_round(&game, player);
+
+        let mut rx = game.subscribe_to();
+        assert_eq!(game.new_round(), Ok(vec![player]));
+        assert_eq!(game.get_round(), 2);
+        assert_eq!(game.get_state(), GameState::Running);
+        assert!(game.get_picks().is_empty());
+        assert!(game.get_items().iter().all(|item| !item.picked));
+        assert!(game.get_winners().is_empty());
+        assert_eq!(game.get_rounds().len(), 1);
+        assert_eq!(game.get_rounds()[0].round, 1);
+        assert_eq!(game.get_rounds()[0].winners[0].player, player);
+
+        let second_board = game.get_player(player).unwrap();
+        assert_eq!(second_board.seed() == first_board.seed(), keep_boards);
+
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.round, 2);
+        assert!(matches!(event.event, ServerEvent::NewRound));
+
+        play_round(&game, player);
+        game.new_round().unwrap();
+        assert_eq!(game.get_round(), 3);
+        assert_eq!(game.get_rounds().iter().map(|r| r.round).collect::<Vec<_>>(), [1, 2]);
+    }
+}
+
+/// a game with a board that has the items in order for `first`, and one for
+/// `second` that completes its first line, the first row, once items 5 to 9
+/// are picked, with items 0 to 4 spread over it without forming any line
+fn spread_boards(options: GameOptions, first: Ulid, second: Ulid) -> Game {
+    let game = test_game_with(25, options);
+    game.start().unwrap();
+    let mut spread: Vec<usize> = (5..25).collect();
+    for (cell, item) in [5, 9, 11, 17, 23].into_iter().zip(0..5) {
+        spread.insert(cell, item);
+    }
+    game.players.write().insert(first, PlayerData::from_parts(0, (0..25).map(Some).collect(), None));
+    game.players.write().insert(second, PlayerData::from_parts(0, spread.into_iter().map(Some).collect(), None));
+    game
+}
+
+#[test]
+fn test_scoring() {
+    let scoring = Scoring { first_line: 100, line: 50, blackout: 250, speed_bonus: 25, speed_picks: 5 };
+    let options = GameOptions { scoring, patterns: vec![WinPattern::Line, WinPattern::Blackout], ..Default::default() };
+    let (first, second) = (Ulid::new(), Ulid::new());
+    let game = spread_boards(options, first, second);
+
+    for idx in 0..10 {
+        game.pick_item(idx).unwrap();
+    }
+    assert_eq!(game.get_scores(), [(first, 175), (second, 50)], "a player's second line should score too");
+    assert_eq!(game.claim_bingo(first), Err(GameError::AlreadyWon));
+
+    for idx in 10..24 {
+        game.pick_item(idx).unwrap();
+    }
+    assert_eq!(game.get_state(), GameState::Running, "nobody has a blackout yet");
+    game.pick_item(24).unwrap();
+    assert_eq!(game.get_state(), GameState::Finished, "everyone won all they could");
+    // every one of the 12 lines and the blackout, the first line and the
+    // speed bonus only once
+    assert_eq!(game.get_scores(), [(first, 100 + 25 + 11 * 50 + 250), (second, 12 * 50 + 250)]);
+}
+
+#[actix_web::test]
+async fn test_leaderboard() {
+    let manager = GamesManager::new();
+    let game = manager.new_game(test_game());
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(manager))
+            .service(resource("/{id}/leaderboard").get(get_leaderboard))
+    ).await;
+
+    let (winner, loser) = (Ulid::new(), Ulid::new());
+    game.add_new_player(winner).unwrap();
+    game.start().unwrap();
+    let row: Vec<usize> = {
+        let board = game.get_player(winner).unwrap();
+        Line::Row(0).cells(5).filter_map(|cell| board.board()[cell]).collect()
+    };
+    // a board with a single item that is never picked
+    let never_picked = (0..25).find(|idx| !row.contains(idx)).unwrap();
+    game.players.write().insert(loser, PlayerData::from_parts(0, vec![Some(never_picked); 25].into_boxed_slice(), None));
+
+    let mut rx = game.subscribe_to();
+    for idx in &row {
+        game.pick_item(*idx).unwrap();
+    }
+    assert_eq!(game.get_state(), GameState::Finished);
+
+    let mut changes = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let ServerEvent::ScoresChanged { changes: c } = event.event {
+            changes.extend(c);
+        }
+    }
+    assert!(changes.iter().any(|c| c.player == winner && c.delta > 0 && c.score as i64 == c.delta));
+
+    let req = TestRequest::get().uri(&format!("/{}/leaderboard", game.get_id()));
+    let leaderboard: serde_json::Value = test::call_and_read_body_json(&app, req.to_request()).await;
+    let leaderboard = leaderboard.as_array().unwrap();
+    assert_eq!(leaderboard.len(), 2);
+    assert_eq!(leaderboard[0]["rank"], 1);
+    assert_eq!(leaderboard[0]["player"], winner.to_string());
+    assert!(leaderboard[0]["score"].as_u64().unwrap() > 0);
+    assert_eq!(leaderboard[1]["score"], 0);
+    assert_eq!(leaderboard[1]["rank"], 2);
+}
+
+#[test]
+fn test_later_lines() {
+    let (first, second) = (Ulid::new(), Ulid::new());
+    let game = spread_boards(Default::default(), first, second);
+    for idx in 0..5 {
+        game.pick_item(idx).unwrap();
+    }
+    assert_eq!(game.get_state(), GameState::Finished, "later lines don't score by default, so the first one ends the round");
+
+    let options = GameOptions { patterns: vec![WinPattern::Line, WinPattern::Blackout], ..Default::default() };
+    let game = spread_boards(options, first, second);
+    for idx in 0..10 {
+        game.pick_item(idx).unwrap();
+    }
+    assert_eq!(game.get_state(), GameState::Running, "a blackout can still be won");
+    assert_eq!(game.get_scores(), [(first, 125), (second, 25)], "later lines are only worth the speed bonus by default");
+
+    game.unpick_item(9).unwrap();
+    let winners = game.get_winners();
+    assert_eq!(winners.len(), 1, "only the lines that needed the item should be revoked");
+    assert_eq!(winners[0].win.line, Some(Line::Row(0)));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_auto_pick() {
+    let manager = GamesManager::new();
+    let options = GameOptions { auto_pick_interval: Some(1), ..Default::default() };
+    let game = manager.new_game(test_game_with(25, options));
+    let mut rx = game.subscribe_to();
+    game.start().unwrap();
+
+    let mut countdown = false;
+    let picked = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match rx.recv().await.unwrap().event {
+                ServerEvent::Countdown { seconds } => countdown = seconds == 1,
+                ServerEvent::NewBall { idx } => break idx,
+                _ => (),
+            }
+        }
+    }).await.unwrap();
+    assert!(countdown, "every pick should be announced");
+    assert!(game.get_items()[picked].picked);
+
+    game.pause().unwrap();
+    let picks = game.get_picks().len();
+    tokio::time::advance(Duration::from_millis(2500)).await;
+    tokio::task::yield_now().await;
+    assert_eq!(game.get_picks().len(), picks, "nothing should be picked while paused");
+
+    manager.remove_game(game.get_id());
+    tokio::task::yield_now().await;
+    assert_eq!(Arc::strong_count(&game), 1, "the auto picker should be gone with the game");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_auto_pick_runs_out() {
+    let manager = GamesManager::new();
+    let options = GameOptions { auto_pick_interval: Some(1), win_mode: WinMode::Claim, ..Default::default() };
+    let game = manager.new_game(test_game_with(25, options));
+    game.start().unwrap();
+    for idx in 0..24 {
+        game.pick_item(idx).unwrap();
+    }
+    let mut rx = game.subscribe_to();
+
+    let ended = tokio::time::timeout(Duration::from_secs(6), async {
+        while !matches!(rx.recv().await.unwrap().event, ServerEvent::StateChanged { state: GameState::Finished }) {}
+    }).await;
+    assert!(ended.is_ok(), "the round should end once nothing is left to pick");
+    assert_eq!(game.get_picks().len(), 25);
+
+    let idle = game.idle_time();
+    game.send_event(ServerEvent::Countdown { seconds: 1 });
+    assert!(game.idle_time() >= idle, "countdowns aren't activity");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_voting() {
+    let manager = GamesManager::new();
+    let options = GameOptions { voting: Voting { threshold: None, window: 1 }, ..Default::default() };
+    let game = manager.new_game(test_game_with(25, options));
+    let players: Vec<Ulid> = (0..3).map(|_| Ulid::new()).collect();
+    for player in &players {
+        game.add_new_player(*player).unwrap();
+    }
+    assert_eq!(game.open_poll(0).err(), Some(GameError::NotRunning));
+    game.start().unwrap();
+    let mut rx = game.subscribe_to();
+
+    let poll = manager.open_poll(&game, 0).unwrap();
+    assert_eq!(poll.needed, 2, "a majority of the players should be needed");
+    assert_eq!(game.open_poll(1).err(), Some(GameError::PollOpen));
+    assert!(matches!(rx.try_recv().unwrap().event, ServerEvent::PollOpened { idx: 0, needed: 2, .. }));
+
+    assert_eq!(game.vote(Ulid::new(), poll.poll, true), Err(GameError::NotInGame));
+    assert_eq!(game.vote(players[0], Ulid::new(), true), Err(GameError::NoSuchPoll));
+    assert_eq!(game.vote(players[0], poll.poll, false), Ok(false));
+    assert_eq!(game.vote(players[0], poll.poll, true), Err(GameError::AlreadyVoted));
+    assert!(matches!(rx.try_recv().unwrap().event, ServerEvent::VoteTally { yes: 0, no: 1, needed: 2, .. }));
+    assert_eq!(game.vote(players[1], poll.poll, true), Ok(false));
+    assert_eq!(game.vote(players[2], poll.poll, true), Ok(true));
+    assert!(game.get_items()[0].picked);
+    let events: Vec<ServerEvent> = std::iter::from_fn(|| rx.try_recv().ok().map(|e| e.event)).collect();
+    assert!(matches!(events[..], [
+        ServerEvent::VoteTally { yes: 1, .. },
+        ServerEvent::VoteTally { yes: 2, .. },
+        ServerEvent::NewBall { idx: 0 },
+        ServerEvent::PollClosed { idx: 0, picked: true, .. },
+        ..
+    ]), "{events:?}");
+    assert_eq!(game.vote(players[1], poll.poll, true), Err(GameError::NoSuchPoll), "the poll should be closed");
+    assert_eq!(game.open_poll(0).err(), Some(GameError::AlreadyPicked));
+
+    // polls without enough votes close once their window is over
+    let poll = manager.open_poll(&game, 1).unwrap();
+    game.vote(players[0], poll.poll, true).unwrap();
+    let closed = tokio::time::timeout(Duration::from_secs(3), async {
+        loop {
+            if let ServerEvent::PollClosed { poll, picked, .. } = rx.recv().await.unwrap().event {
+                break (poll, picked);
+            }
+        }
+    }).await.unwrap();
+    assert_eq!(closed, (poll.poll, false));
+    assert!(!game.get_items()[1].picked);
+
+    // picking the item by hand decides the poll as well
+    let poll = manager.open_poll(&game, 2).unwrap();
+    game.pick_item(2).unwrap();
+    assert_eq!(game.vote(players[0], poll.poll, true), Err(GameError::NoSuchPoll));
+
+    // and so does ending the game
+    let poll = manager.open_poll(&game, 3).unwrap();
+    let mut rx = game.subscribe_to();
+    game.end().unwrap();
+    let events: Vec<ServerEvent> = std::iter::from_fn(|| rx.try_recv().ok().map(|e| e.event)).collect();
+    assert!(events.iter().any(|e| matches!(e, ServerEvent::PollClosed { poll: p, picked: false, .. } if *p == poll.poll)), "{events:?}");
+    game.new_round().unwrap();
+    assert!(manager.open_poll(&game, 3).is_ok(), "the old poll shouldn't be open anymore");
+}
+
+#[actix_web::test]
+async fn test_suggestions() {
+    let manager = GamesManager::new();
+    let options = GameOptions { suggestion_limit: 2, ..Default::default() };
+    let host = Ulid::new();
+    let game = test_game_with(25, options);
+    let game = manager.new_game(game.with_creator(host));
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(manager))
+            .service(resource("/{id}/suggestions").post(suggest_item))
+            .service(resource("/{id}/suggestions/review").post(review_suggestion))
+    ).await;
+
+    let player = Ulid::new();
+    let suggest_req = |player, text: &str| TestRequest::post()
+        .uri(&format!("/{}/suggestions", game.get_id()))
+        .cookie(auth_cookie(player))
+        .set_json(SuggestRequest { text: text.to_owned() });
+
+    let resp = test::call_service(&app, suggest_req(player, "chat spams emotes").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND, "only players can suggest items");
+
+    game.add_new_player(player).unwrap();
+    let suggestion: Suggestion = test::call_and_read_body_json(&app, suggest_req(player, " chat spams emotes ").to_request()).await;
+    assert_eq!(suggestion.text, "chat spams emotes");
+    assert_eq!(suggestion.status, SuggestionStatus::Pending);
+
+    assert_eq!(game.suggest_item(player, "Chat  SPAMS emotes").err(), Some(GameError::DuplicateItem));
+    assert_eq!(game.suggest_item(player, "12").err(), Some(GameError::DuplicateItem), "items already in the game are duplicates too");
+    assert_eq!(game.suggest_item(player, "   ").err(), Some(GameError::EmptyItem));
+    assert_eq!(game.suggest_item(player, &"a".repeat(101)).err(), Some(GameError::ItemTooLong));
+    let rejected = game.suggest_item(player, "streamer sings").unwrap();
+    assert_eq!(game.suggest_item(player, "streamer dances").err(), Some(GameError::TooManySuggestions));
+
+    let review_req = |user, suggestion, approved| TestRequest::post()
+        .uri(&format!("/{}/suggestions/review", game.get_id()))
+        .cookie(auth_cookie(user))
+        .set_json(ReviewRequest { suggestion, approved });
+    let resp = test::call_service(&app, review_req(player, suggestion.id, true).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN, "players can't review their own suggestions");
+    let resp = test::call_service(&app, review_req(host, suggestion.id, true).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(&*game.get_items()[25].text, "chat spams emotes");
+    assert_eq!(game.review_suggestion(rejected.id, false), Ok(None));
+    assert_eq!(game.get_items().len(), 26);
+    let resp = test::call_service(&app, review_req(host, suggestion.id, false).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND, "suggestions can only be reviewed once");
+
+    game.start().unwrap();
+    assert_eq!(game.suggest_item(player, "streamer dances").err(), Some(GameError::SuggestionsClosed));
+}
+
+#[actix_web::test]
+async fn test_update() {
+    let manager = GamesManager::new();
+    let host = Ulid::new();
+    let game = test_game();
+    let game = manager.new_game(game.with_creator(host));
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(manager))
+            .service(resource("/{id}").patch(update_game))
+    ).await;
+
+    let update_req = |body: serde_json::Value| TestRequest::patch()
+        .uri(&format!("/{}", game.get_id()))
+        .cookie(auth_cookie(host))
+        .set_json(body);
+    let error = |resp| async { test::read_body_json::<serde_json::Value, _>(resp).await["error"].clone() };
+
+    let player_req = TestRequest::patch()
+        .uri(&format!("/{}", game.get_id()))
+        .cookie(auth_cookie(Ulid::new()))
+        .set_json(json!({ "title": "mine now" }));
+    let resp = test::call_service(&app, player_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert_eq!(error(resp).await, "forbidden");
+
+    let resp = test::call_service(&app, update_req(json!({ "size": 7 })).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error(resp).await, "not_enough_items");
+    let resp = test::call_service(&app, update_req(json!({ "title": "a".repeat(101) })).to_request()).await;
+    assert_eq!(error(resp).await, "title_too_long");
+    let resp = test::call_service(&app, update_req(json!({ "options": { "free_space": 3 } })).to_request()).await;
+    assert_eq!(error(resp).await, "invalid_options");
+    let resp = test::call_service(&app, update_req(json!({ "options": { "voting": { "window": 0 } } })).to_request()).await;
+    assert_eq!(error(resp).await, "invalid_voting");
+    let resp = test::call_service(&app, update_req(json!({ "options": { "voting": { "threshold": 0 } } })).to_request()).await;
+    assert_eq!(error(resp).await, "invalid_voting");
+    assert_eq!(game.get_size(), 5, "rejected edits shouldn't change anything");
+
+    // the last item is removed and the rest are reversed
+    let items: Vec<String> = (0..24).rev().map(|i| i.to_string()).collect();
+    let body = json!({
+        "title": "stream bingo",
+        "items": items,
+        "options": { "free_space": true, "scoring": { "line": 10 } },
+    });
+    let resp = test::call_service(&app, update_req(body).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(game.get_info().title.as_deref(), Some("stream bingo"));
+    assert_eq!(game.get_items().iter().map(|i| i.text.to_string()).collect::<Vec<_>>(), items);
+    let options = game.get_options();
+    assert!(options.free_space);
+    assert_eq!(options.scoring, Scoring { line: 10, ..Default::default() }, "options should be merged");
+
+    let resp = test::call_service(&app, update_req(json!({ "title": "" })).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(game.get_info().title, None);
+
+    game.start().unwrap();
+    let resp = test::call_service(&app, update_req(json!({ "title": "too late" })).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    assert_eq!(error(resp).await, "already_dealt");
+}
+
+#[actix_web::test]
+async fn test_info() {
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(GamesManager::new()))
+            .service(resource("/create").post(create_game))
+            .service(resource("/get").get(get_game))
+    ).await;
+
+    let create_req = |info: serde_json::Value| {
+        let mut body = json!({ "items": (0..25).map(|i| i.to_string()).collect::<Vec<_>>(), "size": 5 });
+        body.as_object_mut().unwrap().extend(info.as_object().unwrap().clone());
+        TestRequest::post().uri("/create").cookie(auth_cookie(Ulid::new())).set_json(body)
+    };
+
+    let info = json!({
+        "title": "  Just Chatting bingo ",
+        "description": "everything that happens\non stream tonight",
+        "cover_url": "https://static-cdn.jtvnw.net/emoticons/v2/25/default/dark/3.0",
+        "language": "pt-BR",
+        "tags": ["English", "JustChatting"],
+    });
+    let created: CreatedGame = test::call_and_read_body_json(&app, create_req(info).to_request()).await;
+    let get_req = TestRequest::get().uri(&format!("/get?id={}", created.id));
+    let game: serde_json::Value = test::call_and_read_body_json(&app, get_req.to_request()).await;
+    assert_eq!(game["title"], "Just Chatting bingo");
+    assert_eq!(game["language"], "pt-BR");
+    assert_eq!(game["tags"], json!(["English", "JustChatting"]));
+
+    let rejected = [
+        (json!({ "cover_url": "javascript:alert(1)" }), "invalid_cover_url"),
+        (json!({ "language": "portuguese" }), "invalid_language"),
+        (json!({ "tags": ["just chatting"] }), "invalid_tags"),
+        (json!({ "tags": ["bingo", "Bingo"] }), "invalid_tags"),
+        (json!({ "title": "two\nlines" }), "invalid_text"),
+        (json!({ "description": "a".repeat(1001) }), "description_too_long"),
+    ];
+    for (info, error) in rejected {
+        let resp = test::call_service(&app, create_req(info).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], error);
+    }
+
+    let info = GameInfo { title: Some(" ".into()), tags: vec![" tag ".into()], ..Default::default() }.trimmed();
+    assert_eq!(info, GameInfo { tags: vec!["tag".into()], ..Default::default() });
+}
+
+#[test]
+fn test_authorize() {
+    let host = Ulid::new();
+    let game = test_game().with_creator(host);
+    let claims = |user, kind| Claims::new(user, kind, Duration::from_secs(60));
+    assert_eq!(game.authorize(&claims(host, UserKind::Player), Permission::ManageRoles), Ok(()));
+    assert_eq!(game.authorize(&claims(Ulid::new(), UserKind::Admin), Permission::ManageRoles), Ok(()));
+    assert_eq!(game.authorize(&claims(Ulid::new(), UserKind::Host), Permission::RunBoard), Err(GameError::Forbidden), "other hosts can't run the game");
+    assert_eq!(game.authorize(&claims(Ulid::new(), UserKind::Player), Permission::Play), Ok(()));
+
+    let legacy = test_game();
+    assert_eq!(legacy.authorize(&claims(host, UserKind::Player), Permission::RunBoard), Err(GameError::Forbidden));
+}
+
+#[actix_web::test]
+async fn test_private() {
+    let manager = Data::new(GamesManager::new());
+    let app = test::init_service(
+        App::new()
+            .app_data(manager.clone())
+            .service(resource("/create").post(create_game))
+            .service(resource("/get").get(get_game))
+            .service(resource("/invite/{code}").get(resolve_invite))
+            .service(resource("/{id}/join").post(join_game))
+            .service(resource("/{id}/invite").get(get_invite).post(rotate_invite))
+            .service(resource("/{id}/access").patch(update_access))
+            .service(resource("/{id}/leaderboard").get(get_leaderboard))
+            .service(resource("/{id}/roles").get(get_roles))
+    ).await;
+
+    let host = auth_cookie(Ulid::new());
+    let create_req = TestRequest::post()
+        .uri("/create")
+        .cookie(host.clone())
+        .set_json(CreateGameRequest { visibility: Visibility::Private, password: Some(" hunter2 ".into()), ..create_request((0..30).map(|i| i.to_string()).collect()) });
+    let created: CreatedGame = test::call_and_read_body_json(&app, create_req.to_request()).await;
+    let invite = created.invite.unwrap();
+    assert_eq!(invite.len(), INVITE_LENGTH);
+
+    let resolve_req = TestRequest::get().uri(&format!("/invite/{}", invite.to_lowercase()));
+    let resolved: serde_json::Value = test::call_and_read_body_json(&app, resolve_req.to_request()).await;
+    assert_eq!(resolved["id"], created.id.to_string(), "invite codes should be resolved in any case");
+
+    let get_req = TestRequest::get().uri(&format!("/get?id={}", created.id));
+    let resp = test::call_service(&app, get_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let get_req = TestRequest::get().uri(&format!("/get?id={}", created.code.unwrap())).insert_header((ACCESS_KEY_HEADER, "hunter2"));
+    let game: serde_json::Value = test::call_and_read_body_json(&app, get_req.to_request()).await;
+    assert_eq!(game["id"], created.id.to_string(), "games should be found by their join code, and the password trimmed");
+    let password_hash = manager.get_game(created.id).unwrap().get_access().password_hash.unwrap();
+    assert!(password_hash.starts_with("$argon2") && !password_hash.contains("hunter2"), "passwords should only be kept hashed");
+    let get_req = TestRequest::get().uri(&format!("/get?id={}&key=hunter2", created.id));
+    let resp = test::call_service(&app, get_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN, "keys aren't taken from the URL");
+
+    for path in ["leaderboard", "roles"] {
+        let req = TestRequest::get().uri(&format!("/{}/{path}", created.id));
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN, "{path} shouldn't be readable without the key");
+        let req = TestRequest::get().uri(&format!("/{}/{path}", created.id)).insert_header((ACCESS_KEY_HEADER, invite.as_str()));
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK, "{path} should be readable with the key");
+        let req = TestRequest::get().uri(&format!("/{}/{path}", created.id)).cookie(host.clone());
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK, "the host doesn't need the key for {path}");
+    }
+
+    let player = auth_cookie(Ulid::new());
+    let join_req = TestRequest::post().uri(&format!("/{}/join", created.id)).cookie(player.clone());
+    let resp = test::call_service(&app, join_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let join_req = TestRequest::post().uri(&format!("/{}/join", created.id)).insert_header((ACCESS_KEY_HEADER, invite.as_str())).cookie(player.clone());
+    let resp = test::call_service(&app, join_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let rotate_req = TestRequest::post().uri(&format!("/{}/invite", created.id)).cookie(player.clone());
+    let resp = test::call_service(&app, rotate_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN, "only the host can rotate the invite code");
+    let rotate_req = TestRequest::post().uri(&format!("/{}/invite", created.id)).cookie(host.clone());
+    let rotated: serde_json::Value = test::call_and_read_body_json(&app, rotate_req.to_request()).await;
+    assert_ne!(rotated["code"], invite);
+    let get_invite_req = TestRequest::get().uri(&format!("/{}/invite", created.id)).cookie(host.clone());
+    let current: serde_json::Value = test::call_and_read_body_json(&app, get_invite_req.to_request()).await;
+    assert_eq!(current, rotated);
+
+    let resolve_req = TestRequest::get().uri(&format!("/invite/{invite}"));
+    let resp = test::call_service(&app, resolve_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND, "the old invite code should stop working");
+    let join_req = TestRequest::post().uri(&format!("/{}/join", created.id)).insert_header((ACCESS_KEY_HEADER, invite.as_str())).cookie(auth_cookie(Ulid::new()));
+    let resp = test::call_service(&app, join_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let join_req = TestRequest::post().uri(&format!("/{}/join", created.id)).cookie(player);
+    let resp = test::call_service(&app, join_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED, "players who already joined don't need the new code");
+
+    let access_req = TestRequest::patch()
+        .uri(&format!("/{}/access", created.id))
+        .cookie(host)
+        .set_json(json!({ "visibility": "unlisted", "password": "" }));
+    let resp = test::call_service(&app, access_req.to_request()).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let get_req = TestRequest::get().uri(&format!("/get?id={}", created.id));
+    let game: serde_json::Value = test::call_and_read_body_json(&app, get_req.to_request()).await;
+    assert_eq!(game["visibility"], "unlisted");
+    assert_eq!(manager.get_game(created.id).unwrap().get_access().password_hash, None);
+}
+
+#[test]
+fn test_codes() {
+    let codes = Codes::default();
+    let game = Ulid::new();
+    let code = codes.issue(game, 6);
+    assert!(code.bytes().all(|c| ALPHABET.contains(&c)));
+    let spaced = format!("{}-{} ", code[..3].to_lowercase(), &code[3..]);
+    assert_eq!(codes.resolve(&spaced), Some(game));
+
+    assert!(codes.restore(&code, game));
+    assert!(!codes.restore(&code, Ulid::new()), "codes can't belong to two games");
+    codes.release(&code);
+    assert_eq!(codes.resolve(&code), None);
+
+    let manager = GamesManager::new();
+    let game = manager.new_game(test_game());
+    let code = game.get_code().unwrap();
+    assert_eq!(code.len(), CODE_LENGTH);
+    assert_eq!(manager.find_game(&code.to_lowercase()).map(|g| g.get_id()), Some(game.get_id()));
+    assert_eq!(manager.find_game(&game.get_id().to_string()).map(|g| g.get_id()), Some(game.get_id()));
+    manager.remove_game(game.get_id());
+    assert!(manager.find_game(&code).is_none(), "codes should be released with their games");
+}
+
+#[test]
+fn test_spectators() {
+    let game = Arc::new(test_game());
+    let mut rx = game.subscribe_to();
+    let first = game.spectate(Some(2)).unwrap();
+    let second = game.spectate(Some(2)).unwrap();
+    assert_eq!(game.spectate(Some(2)).unwrap_err(), GameError::TooManySpectators);
+    assert_eq!(GameError::TooManySpectators.status_code(), StatusCode::CONFLICT, "a full game isn't the server being unavailable");
+    assert_eq!(game.spectator_count(), 2);
+    assert_eq!(game.player_count(), 0, "spectators aren't players");
+    assert!(game.get_player(Ulid::new()).is_none());
+
+    drop(first);
+    assert_eq!(game.spectator_count(), 1);
+    let _third = game.spectate(Some(2)).unwrap();
+    drop(second);
+    assert!(game.spectate(None).is_ok(), "there's no limit without a cap");
+
+    let player = Ulid::new();
+    game.add_new_player(player).unwrap();
+    assert_eq!(game.player_count(), 1);
+    game.start().unwrap();
+    game.pick_item(0).unwrap();
+    game.send_event(ServerEvent::ClaimRejected { player });
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event.event);
+    }
+    let watched: Vec<&ServerEvent> = events.iter().filter(|e| e.for_spectators()).collect();
+    assert!(watched.iter().any(|e| matches!(e, ServerEvent::NewBall { .. })));
+    assert!(watched.iter().any(|e| matches!(e, ServerEvent::StateChanged { state: GameState::Running })));
+    assert!(!watched.iter().any(|e| matches!(e, ServerEvent::ClaimRejected { .. })), "spectators don't get what players do");
+    let rejected = ServerEvent::ClaimRejected { player };
+    assert_eq!(rejected.recipient(), Some(player), "only the player hears about their own claim");
+    assert_eq!(ServerEvent::NewBall { idx: 0 }.recipient(), None);
+}
+
+#[test]
+fn test_roles() {
+    let owner = Ulid::new();
+    let game = test_game().with_creator(owner);
+    let (co_host, moderator, spectator) = (Ulid::new(), Ulid::new(), Ulid::new());
+    let mut rx = game.subscribe_to();
+
+    assert_eq!(game.set_role(owner, Role::CoHost), Err(GameError::InvalidRole), "the owner can't be demoted");
+    assert_eq!(game.set_role(co_host, Role::Owner), Err(GameError::InvalidRole), "ownership can't be granted");
+    game.set_role(co_host, Role::CoHost).unwrap();
+    game.set_role(moderator, Role::Moderator).unwrap();
+    game.set_role(spectator, Role::Spectator).unwrap();
+    game.set_role(spectator, Role::Spectator).unwrap();
+    assert_eq!(game.role_of(owner), Role::Owner);
+    assert_eq!(game.role_of(Ulid::new()), Role::Player);
+
+    assert_eq!(game.check_role(co_host, Permission::Host), Ok(()));
+    assert_eq!(game.check_role(co_host, Permission::ManageRoles), Err(GameError::Forbidden));
+    assert_eq!(game.check_role(moderator, Permission::RunBoard), Ok(()));
+    assert_eq!(game.check_role(moderator, Permission::Host), Err(GameError::Forbidden));
+    assert_eq!(game.add_new_player(moderator), Ok(true), "moderators can still play");
+    assert_eq!(game.add_new_player(spectator), Err(GameError::Forbidden));
+
+    game.add_new_player(co_host).unwrap();
+    game.start().unwrap();
+    game.set_role(co_host, Role::Spectator).unwrap();
+    assert_eq!(game.claim_bingo(co_host), Err(GameError::Forbidden), "players who become spectators stop playing");
+    game.set_role(co_host, Role::Player).unwrap();
+    assert!(game.get_roles().get(&co_host).is_none());
+    assert!(matches!(game.claim_bingo(co_host), Ok(_) | Err(GameError::NoBingo)));
+
+    let mut changes = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let ServerEvent::RoleChanged { user, role } = event.event {
+            changes.push((user, role));
+        }
+    }
+    assert_eq!(changes, vec![
+        (co_host, Role::CoHost),
+        (moderator, Role::Moderator),
+        (spectator, Role::Spectator),
+        (co_host, Role::Spectator),
+        (co_host, Role::Player),
+    ], "only actual changes are sent");
+}
@@ -0,0 +1,115 @@
+use actix_web::{web::{Data, Json, Path}, HttpResponse, HttpResponseBuilder, ResponseError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{create::CreateError, error::GameError, manager::GamesManager, role::Permission};
+
+/// changes to a game in the lobby, everything that is left out stays the same
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct UpdateGameRequest {
+    /// the new title, an empty one removes it, the same goes for the
+    /// description, cover and language
+    #[cfg_attr(feature="swagger-ui", schema(example = "Just Chatting bingo"))]
+    pub(super) title: Option<String>,
+    pub(super) description: Option<String>,
+    pub(super) cover_url: Option<String>,
+    pub(super) language: Option<String>,
+    /// the tags replacing the current ones
+    pub(super) tags: Option<Vec<String>>,
+    /// every item of the game in their new order, replacing the current ones
+    pub(super) items: Option<Vec<String>>,
+    #[cfg_attr(feature="swagger-ui", schema(minimum = 5, maximum = 23))]
+    pub(super) size: Option<u32>,
+    /// the options to change, the ones that are left out keep their current
+    /// values and the ones set to `null` go back to their defaults
+    #[cfg_attr(feature="swagger-ui", schema(value_type = Object, example = json!({"free_space": true, "auto_pick_interval": null})))]
+    pub(super) options: Option<Value>,
+}
+
+/// why an edit to a game was rejected, either tagged as `error`
+#[derive(Debug, Clone, Copy, Serialize, thiserror::Error)]
+#[serde(untagged)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum UpdateError {
+    /// the game wouldn't be valid anymore after the edit
+    #[error(transparent)]
+    Invalid(#[from] CreateError),
+    #[error(transparent)]
+    Game(#[from] GameError),
+}
+
+impl ResponseError for UpdateError {
+    fn status_code(&self) -> actix_web::http::StatusCode {
+        match self {
+            UpdateError::Invalid(e) => e.status_code(),
+            UpdateError::Game(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponseBuilder::new(self.status_code()).json(self)
+    }
+}
+
+/// applies the JSON merge patch `patch` to `target`, objects are merged key by
+/// key and `null` removes a key
+pub(super) fn merge(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            match value {
+                Value::Null => { target.remove(&key); },
+                value => merge(target.entry(key).or_insert(Value::Null), value),
+            }
+        }
+    }
+}
+
+/// edit a game before any boards are dealt
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    patch,
+    path = "/game/{id}",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body(
+        content = UpdateGameRequest,
+        description = "The changes to the game, which are checked the same way as a new game"
+    ),
+    responses(
+        (status = 204, description = "The game was edited and all players were notified"),
+        (status = 400, description = "The game wouldn't be valid after the edit", body = UpdateError),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "Boards were already dealt", body = GameError)
+    )
+))]
+pub async fn update_game(
+    claims: Claims,
+    id: Path<Ulid>,
+    update: Json<UpdateGameRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<HttpResponse, UpdateError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::Host)?;
+
+    game.update(update.into_inner())?;
+    games_manager.restart_auto_pick(&game);
+    games_manager.save_game(&game);
+
+    return Ok(HttpResponse::NoContent().finish());
+}
@@ -0,0 +1,143 @@
+use std::{sync::Weak, time::Duration};
+
+use actix_web::web::{Data, Json, Path};
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::auth::jwt::Claims;
+
+use super::{error::GameError, manager::GamesManager, pick::PickRequest, role::Permission, Game};
+
+/// how chat votes on the items that get picked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Voting {
+    /// yes votes an item needs to be picked, a majority of the players if
+    /// left out
+    #[cfg_attr(feature="swagger-ui", schema(example = 3, minimum = 1))]
+    pub threshold: Option<u32>,
+    /// seconds a poll stays open before it's closed without a pick
+    #[cfg_attr(feature="swagger-ui", schema(example = 60, minimum = 1))]
+    pub window: u32,
+}
+
+impl Default for Voting {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            window: 60,
+        }
+    }
+}
+
+impl Voting {
+    /// how many yes votes are needed in a game with `players` players
+    pub fn needed(&self, players: usize) -> u32 {
+        self.threshold.unwrap_or(players as u32 / 2 + 1).max(1)
+    }
+}
+
+/// a vote on whether the item at `idx` should be picked, only one can be open
+/// in a game at a time
+#[derive(Debug, Clone)]
+pub struct Poll {
+    pub id: Ulid,
+    pub idx: usize,
+    /// yes votes needed for the item to be picked
+    pub needed: u32,
+    yes: HashSet<Ulid>,
+    no: HashSet<Ulid>,
+}
+
+impl Poll {
+    pub fn new(idx: usize, needed: u32) -> Self {
+        Self {
+            id: Ulid::new(),
+            idx,
+            needed,
+            yes: Default::default(),
+            no: Default::default(),
+        }
+    }
+
+    /// counts the vote of the player with the user ID `player`
+    ///
+    /// returns `None` if they already voted in this poll
+    pub fn vote(&mut self, player: Ulid, yes: bool) -> Option<()> {
+        if self.yes.contains(&player) || self.no.contains(&player) {
+            return None;
+        }
+        match yes {
+            true => self.yes.insert(player),
+            false => self.no.insert(player),
+        };
+        Some(())
+    }
+
+    pub fn tally(&self) -> (u32, u32) {
+        (self.yes.len() as u32, self.no.len() as u32)
+    }
+
+    pub fn passed(&self) -> bool {
+        self.yes.len() as u32 >= self.needed
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct OpenedPoll {
+    /// the ID players vote with
+    #[cfg_attr(feature="swagger-ui", schema(example = "01HPQVN1ENW15AWCYY8VGBKGF1"))]
+    pub poll: Ulid,
+    /// yes votes needed for the item to be picked
+    #[cfg_attr(feature="swagger-ui", schema(example = 3))]
+    pub needed: u32,
+}
+
+/// open a vote on whether an item should be picked, players vote over the
+/// websocket
+#[cfg_attr(feature="swagger-ui", utoipa::path(
+    post,
+    path = "/game/{id}/poll",
+    tag = "Game",
+    security(
+        ("user_token" = ["write:games"])
+    ),
+    params(
+        ("id" = Ulid, Path, description = "the ULID of the game")
+    ),
+    request_body = PickRequest,
+    responses(
+        (status = 200, description = "The poll was opened and all players were notified", body = OpenedPoll),
+        (status = 400, description = "There is no item with that index", body = GameError),
+        (status = 403, description = "The user's role in the game doesn't allow this", body = GameError),
+        (status = 404, description = "No game with that ULID was found", body = GameError),
+        (status = 409, description = "The item was already picked, another poll is open or the game isn't running", body = GameError)
+    )
+))]
+pub async fn open_poll(
+    claims: Claims,
+    id: Path<Ulid>,
+    pick: Json<PickRequest>,
+    games_manager: Data<GamesManager>
+) -> Result<Json<OpenedPoll>, GameError> {
+    let game = games_manager.get_game(*id).ok_or(GameError::NoSuchGame)?;
+    game.authorize(&claims, Permission::RunBoard)?;
+
+    let poll = games_manager.open_poll(&game, pick.idx)?;
+
+    return Ok(Json(poll));
+}
+
+/// closes the poll `poll` in `game` once its window is over, unless it was
+/// decided by then
+///
+/// only a weak reference is kept so the game can go away in the meantime
+pub(super) async fn expire(game: Weak<Game>, poll: Ulid, window: u32) {
+    tokio::time::sleep(Duration::from_secs(window.into())).await;
+    if let Some(game) = game.upgrade() {
+        game.expire_poll(poll);
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use super::Item;
+
+/// a straight line across a bingo board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum Line {
+    /// the row with that index, counting from the top
+    Row(u32),
+    /// the column with that index, counting from the left
+    Column(u32),
+    /// top left to bottom right
+    Diagonal,
+    /// top right to bottom left
+    AntiDiagonal,
+}
+
+impl Line {
+    /// every line on a board of size `size`
+    pub fn all(size: u32) -> impl Iterator<Item = Line> {
+        (0..size).map(Line::Row)
+            .chain((0..size).map(Line::Column))
+            .chain([Line::Diagonal, Line::AntiDiagonal])
+    }
+
+    /// the indices of the cells that make up this line on a board of size `size`
+    pub fn cells(self, size: u32) -> impl Iterator<Item = usize> {
+        let size = size as usize;
+        (0..size).map(move |i| match self {
+            Line::Row(row) => row as usize * size + i,
+            Line::Column(col) => i * size + col as usize,
+            Line::Diagonal => i * size + i,
+            Line::AntiDiagonal => i * size + (size - 1 - i),
+        })
+    }
+}
+
+/// a shape that wins the game once every one of its cells was picked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub enum WinPattern {
+    /// any full row, column or diagonal
+    Line,
+    /// every single cell on the board
+    Blackout,
+    FourCorners,
+    /// both diagonals
+    X,
+    /// the middle row and the middle column
+    Plus,
+    /// every cell along the edges of the board
+    PictureFrame,
+    /// an arbitrary mask the size of the board
+    Custom {
+        /// shown to players when someone wins with this pattern
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// one string per row, `1` marks a cell that is part of the pattern and
+        /// `0` one that isn't
+        #[cfg_attr(feature="swagger-ui", schema(example = json!(["10001", "01010", "00100", "01010", "10001"])))]
+        rows: Vec<String>,
+    },
+}
+
+impl WinPattern {
+    /// whether this pattern can be used on a board of size `size`
+    pub fn fits(&self, size: u32) -> bool {
+        match self {
+            WinPattern::Custom { rows, .. } => {
+                rows.len() == size as usize
+                    && rows.iter().all(|r| r.len() == size as usize && r.chars().all(|c| c == '0' || c == '1'))
+                    && rows.iter().any(|r| r.contains('1'))
+            },
+            _ => true,
+        }
+    }
+
+    /// the indices of the cells that make up this pattern on a board of size
+    /// `size`, [WinPattern::Line] is handled separately since any of its
+    /// lines wins
+    fn cells(&self, size: u32) -> Vec<usize> {
+        let len = size as usize;
+        let middle = len / 2;
+        let all = 0..len * len;
+        match self {
+            WinPattern::Line => Vec::new(),
+            WinPattern::Blackout => all.collect(),
+            WinPattern::FourCorners => vec![0, len - 1, len * (len - 1), len * len - 1],
+            WinPattern::X => all.filter(|c| c / len == c % len || c / len + c % len == len - 1).collect(),
+            WinPattern::Plus => all.filter(|c| c / len == middle || c % len == middle).collect(),
+            WinPattern::PictureFrame => all.filter(|c| {
+                let (row, col) = (c / len, c % len);
+                row == 0 || col == 0 || row == len - 1 || col == len - 1
+            }).collect(),
+            WinPattern::Custom { rows, .. } => rows.iter()
+                .flat_map(|r| r.chars())
+                .enumerate()
+                .filter_map(|(cell, c)| (c == '1').then_some(cell))
+                .collect(),
+        }
+    }
+}
+
+/// what a player's board matched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Win {
+    pub pattern: WinPattern,
+    /// the completed line, only present for [WinPattern::Line]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<Line>,
+}
+
+impl Win {
+    /// whether `board` still completes this win, after picks were undone
+    pub fn holds(&self, board: &[Option<usize>], size: u32, items: &[Item]) -> bool {
+        match (&self.pattern, self.line) {
+            (WinPattern::Line, Some(line)) => is_complete(line.cells(size), board, items),
+            (WinPattern::Line, None) => find_line(board, size, items).is_some(),
+            (pattern, _) => is_complete(pattern.cells(size).into_iter(), board, items),
+        }
+    }
+}
+
+/// a player who won, and how
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature="swagger-ui", derive(utoipa::ToSchema))]
+pub struct Winner {
+    pub player: Ulid,
+    #[serde(flatten)]
+    pub win: Win,
+    /// how many items were picked in the round when the player won
+    #[serde(default)]
+    pub picks: usize,
+}
+
+/// why a win was taken back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum RevokeReason {
+    /// the host took back the pick of the item at `idx`, which the win needed
+    PickUndone {
+        idx: usize,
+    },
+}
+
+fn is_complete(mut cells: impl Iterator<Item = usize>, board: &[Option<usize>], items: &[Item]) -> bool {
+    cells.all(|cell| match board.get(cell) {
+        Some(Some(idx)) => items.get(*idx).is_some_and(|item| item.picked),
+        // the free space
+        Some(None) => true,
+        None => false,
+    })
+}
+
+/// finds the first completed line of `board`, if there is one
+///
+/// `board` holds indices into `items`, row by row, with `None` being the free
+/// space
+pub fn find_line(board: &[Option<usize>], size: u32, items: &[Item]) -> Option<Line> {
+    Line::all(size).find(|line| is_complete(line.cells(size), board, items))
+}
+
+/// every win `board` completed with `patterns`, each completed line counting
+/// as a win of its own
+pub fn find_wins(board: &[Option<usize>], size: u32, items: &[Item], patterns: &[WinPattern]) -> Vec<Win> {
+    let mut wins = Vec::new();
+    for pattern in patterns {
+        match pattern {
+            WinPattern::Line => wins.extend(Line::all(size)
+                .filter(|line| is_complete(line.cells(size), board, items))
+                .map(|line| Win { pattern: WinPattern::Line, line: Some(line) })),
+            _ => if is_complete(pattern.cells(size).into_iter(), board, items) {
+                wins.push(Win { pattern: pattern.clone(), line: None });
+            },
+        }
+    }
+    wins
+}
+
+/// how many wins a single board of size `size` can get with `patterns`
+pub fn max_wins(size: u32, patterns: &[WinPattern]) -> usize {
+    patterns.iter()
+        .map(|pattern| match pattern {
+            WinPattern::Line => Line::all(size).count(),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// finds the first of `patterns` that `board` completed, if there is one
+pub fn find_win(board: &[Option<usize>], size: u32, items: &[Item], patterns: &[WinPattern]) -> Option<Win> {
+    patterns.iter().find_map(|pattern| match pattern {
+        WinPattern::Line => find_line(board, size, items).map(|line| Win {
+            pattern: WinPattern::Line,
+            line: Some(line)
+        }),
+        _ => is_complete(pattern.cells(size).into_iter(), board, items).then(|| Win {
+            pattern: pattern.clone(),
+            line: None
+        }),
+    })
+}
@@ -5,7 +5,7 @@ use actix_web::{
     middleware::{Compress, Logger}, web::{self, Data}
 };
 use bingo_backend::{
-    auth::{self, TwitchAuthMiddleware}, cli, game::{self, manager::GamesManager}, metrics::{prometheus_endpoint, Prometheus}, rate_limiter::{Dummy, InMemory, RateLimiter}, utils::ReqIpAddr, websocket
+    auth, cli, game::{self, manager::{Expiry, GamesManager}, store::GameStore}, metrics::{prometheus_endpoint, Prometheus}, rate_limiter::RateLimiter, websocket
 };
 use env_logger::Env;
 use log::{error, info};
@@ -17,6 +17,12 @@ use {
     utoipa_swagger_ui::SwaggerUi
 };
 
+#[cfg(debug_assertions)]
+use bingo_backend::rate_limiter::Dummy;
+
+#[cfg(not(debug_assertions))]
+use bingo_backend::{rate_limiter::InMemory, utils::ReqIpAddr};
+
 const BIND_ADDRESS: Ipv4Addr = {
     #[cfg(debug_assertions)]
     {
@@ -85,7 +91,29 @@ async fn main() {
         docs
     };
 
-    let manager = Data::new(GamesManager::new());
+    // Loading games that were running before the last shutdown
+
+    let manager = Data::new(
+        GamesManager::with_store(GameStore::new(db_pool.get_ref().clone()))
+            .with_max_spectators(cli::ARGS.max_spectators)
+    );
+
+    match manager.load_games().await {
+        Ok(count) => info!("loaded {count} active games from the database"),
+        Err(e) => {
+            error!("failed to load games from the database: {e}");
+            return;
+        }
+    }
+
+    GamesManager::spawn_sweeper(
+        manager.clone(),
+        Expiry {
+            idle_timeout: Duration::from_secs(cli::ARGS.expiry_args.idle_timeout),
+            max_lifetime: Duration::from_secs(cli::ARGS.expiry_args.max_lifetime),
+        },
+        Duration::from_secs(cli::ARGS.expiry_args.sweep_interval)
+    );
 
     let app_info = Data::new(cli::ARGS.app_info.clone());
 
@@ -49,6 +49,7 @@ where
     }
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
+        #[allow(clippy::expect_used)]
         static TOTAL_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
             register_int_counter_vec!(
                 "http_requests_total",
@@ -62,12 +63,11 @@ where
         let service = self.service.clone();
         Box::pin(async move {
             let resp = service.call(req).await;
-            match &resp {
-                Ok(res) => {
-                    TOTAL_REQUESTS.with_label_values(&[method.as_str(), res.status().clone().as_str(), &path]).inc()
-                },
-                Err(_) => todo!(),
+            let status = match &resp {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
             };
+            TOTAL_REQUESTS.with_label_values(&[method.as_str(), status.as_str(), &path]).inc();
             resp
         })
     }
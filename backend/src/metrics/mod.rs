@@ -1,10 +1,21 @@
 use actix_web::{HttpResponseBuilder, Responder};
-use prometheus::TextEncoder;
+use once_cell::sync::Lazy;
+use prometheus::{register_int_counter_vec, IntCounterVec, TextEncoder};
 use reqwest::StatusCode;
 
 mod middleware;
 pub use middleware::Prometheus;
 
+/// games removed by the sweeper, labeled with why they were removed
+#[allow(clippy::expect_used)]
+pub static GAMES_EVICTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "games_evicted_total",
+        "total games removed from memory by the sweeper",
+        &["reason"]
+    ).expect("failed at initializing games_evicted_total counter")
+});
+
 /// FIXME: limit this so only prometheus can access it
 pub async fn prometheus_endpoint() -> impl Responder {
     match TextEncoder::new().encode_to_string(&prometheus::gather()) {
@@ -1,23 +1,42 @@
 use std::{future::Future, pin::Pin, task::Poll};
 
 use actix::{Actor, ActorFuture};
-use log::error;
-use tokio::sync::broadcast::Receiver;
+use log::{error, warn};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use ulid::Ulid;
 
-use crate::event::ServerEvent;
+use crate::event::GameEvent;
 
 use super::BingoWs;
 
+/// waits for the next event, handing the receiver back along with it
+type RecvFuture = Pin<Box<dyn Future<Output = (Result<GameEvent, RecvError>, Receiver<GameEvent>)>>>;
+
 pub struct EventListener {
-    rx: Receiver<ServerEvent>
+    // the future has to be kept between polls, dropping it would also drop
+    // the waker it registered
+    recv: RecvFuture,
+    /// whether only the events for spectators are sent
+    spectating: bool,
+    /// the logged in user, who's the only one getting events about them alone
+    user: Option<Ulid>,
 }
 
 impl EventListener {
-    pub fn new(rx: Receiver<ServerEvent>) -> Self {
+    pub fn new(rx: Receiver<GameEvent>, spectating: bool, user: Option<Ulid>) -> Self {
         Self {
-            rx
+            recv: Self::recv(rx),
+            spectating,
+            user,
         }
     }
+
+    fn recv(mut rx: Receiver<GameEvent>) -> RecvFuture {
+        Box::pin(async move {
+            let res = rx.recv().await;
+            (res, rx)
+        })
+    }
 }
 
 impl ActorFuture<BingoWs> for EventListener {
@@ -29,19 +48,27 @@ impl ActorFuture<BingoWs> for EventListener {
         ctx: &mut <BingoWs as Actor>::Context,
         task: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        let mut recv_fut = Box::pin(async { self.rx.recv().await });
-        let recv_fut = Pin::new(&mut recv_fut);
-
-        match Future::poll(recv_fut, task) {
-            Poll::Ready(Ok(e)) => {
-                ctx.text(serde_json::to_string(&e).unwrap());
-                Poll::Pending
-            },
-            Poll::Ready(Err(err)) => {
-                error!("{err}");
-                Poll::Ready(())
+        loop {
+            let (res, rx) = match self.recv.as_mut().poll(task) {
+                Poll::Ready(ready) => ready,
+                Poll::Pending => return Poll::Pending,
+            };
+            self.recv = Self::recv(rx);
+
+            match res {
+                Ok(e) if self.spectating && !e.event.for_spectators() => (),
+                Ok(e) if e.event.recipient().is_some_and(|r| Some(r) != self.user) => (),
+                Ok(e) => {
+                    match serde_json::to_string(&e) {
+                        Ok(json) => ctx.text(json),
+                        Err(err) => error!("failed to serialize event: {err}"),
+                    }
+                },
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("websocket fell behind, skipped {skipped} events");
+                },
+                Err(RecvError::Closed) => return Poll::Ready(()),
             }
-            Poll::Pending => Poll::Pending,
         }
     }
 }
@@ -1,40 +1,201 @@
-use std::time::{Duration, Instant};
+use std::{sync::Arc, time::{Duration, Instant}};
 
 use actix::{Actor, ActorContext, AsyncContext, SpawnHandle, StreamHandler};
 use actix_web::{web::{self, Data, Query}, Error, HttpRequest, HttpResponse, HttpResponseBuilder, Responder, ResponseError};
 use actix_web_actors::ws;
 use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast::Receiver;
 use ulid::Ulid;
 
-use crate::{event::{ClientEvent, ServerEvent}, game::manager::GamesManager};
+use crate::{auth::jwt::Claims, event::{ClientEvent, ClientMessage}, game::{access::AccessKey, board::BoardData, error::GameError, manager::GamesManager, role::Permission, spectator::Spectator, Game}};
 
-use self::{event_listener::EventListener, heartbeat::Heartbeat};
+use self::{event_listener::EventListener, heartbeat::Heartbeat, reply::{ProtocolError, Reply, ReplyError}};
 
 mod heartbeat;
 mod event_listener;
+pub mod reply;
+
+#[cfg(test)]
+mod test;
 
 const HEARTBEAT_TIME: Duration = Duration::from_secs(29);
 
 struct BingoWs {
     last_message: Instant,
-    game: Ulid,
-    listener: Option<Receiver<ServerEvent>>,
+    /// the game whose events are sent to the client, if it subscribed to one
+    game: Option<Arc<Game>>,
+    games_manager: Data<GamesManager>,
+    /// the claims of the logged in user, if there is one
+    claims: Option<Claims>,
+    /// whether the client only watches games, without playing or hosting them
+    spectating: bool,
+    /// the client's spot among the spectators of the game, if it's spectating
+    spectator: Option<Spectator>,
     heartbeat_handle: Option<SpawnHandle>,
     listener_handle: Option<SpawnHandle>
 }
 
 impl BingoWs {
-    pub(self) fn new(receiver: Receiver<ServerEvent>, game_ulid: Ulid) -> Self {
+    pub(self) fn new(game: Option<Arc<Game>>, games_manager: Data<GamesManager>, claims: Option<Claims>) -> Self {
         Self {
             last_message: Instant::now(),
-            game: game_ulid,
-            listener: Some(receiver),
+            game,
+            games_manager,
+            claims,
+            spectating: false,
+            spectator: None,
             heartbeat_handle: None,
             listener_handle: None
         }
     }
+
+    /// makes the client a spectator, `spectator` is its spot among the
+    /// spectators of the game it starts out subscribed to
+    pub(self) fn with_spectator(mut self, spectator: Option<Spectator>) -> Self {
+        self.spectating = true;
+        self.spectator = spectator;
+        self
+    }
+
+    /// starts sending the events of `game` to the client, instead of the ones
+    /// of the game it was subscribed to before
+    fn subscribe(&mut self, game: Arc<Game>, ctx: &mut <Self as Actor>::Context) {
+        if let Some(h) = self.listener_handle.take() {
+            ctx.cancel_future(h);
+        }
+        let user = self.claims.as_ref().map(Claims::user_id);
+        self.listener_handle = Some(ctx.spawn(EventListener::new(game.subscribe_to(), self.spectating, user)));
+        self.game = Some(game);
+    }
+
+    fn user(&self) -> Result<Ulid, ReplyError> {
+        self.claims.as_ref().map(Claims::user_id).ok_or(GameError::Unauthenticated.into())
+    }
+
+    fn game(&self) -> Result<&Arc<Game>, ReplyError> {
+        self.game.as_ref().ok_or(ProtocolError::NotSubscribed.into())
+    }
+
+    /// the game the client is subscribed to, as long as the user's role in it
+    /// allows `permission`
+    fn authorized_game(&self, permission: Permission) -> Result<&Arc<Game>, ReplyError> {
+        let claims = self.claims.as_ref().ok_or(GameError::Unauthenticated)?;
+        let game = self.game()?;
+        game.authorize(claims, permission)?;
+        Ok(game)
+    }
+
+    fn handle_event(&mut self, event: ClientEvent, ctx: &mut <Self as Actor>::Context) -> Result<Option<BoardData>, ReplyError> {
+        if self.spectating && !matches!(event, ClientEvent::Ping | ClientEvent::Subscribe { .. }) {
+            return Err(ProtocolError::Spectating.into());
+        }
+        match event {
+            ClientEvent::Join => {
+                let user = self.user()?;
+                let game = self.game()?;
+                if game.add_new_player(user)? {
+                    self.games_manager.save_player(game, user);
+                }
+                Ok(game.get_player(user).map(|player| BoardData::new(game, &player)))
+            },
+            ClientEvent::MarkCell { cell, marked } => {
+                let user = self.user()?;
+                let game = self.game()?;
+                game.mark_cell(user, cell, marked)?;
+                self.games_manager.save_player(game, user);
+                Ok(None)
+            },
+            ClientEvent::Suggest { text } => {
+                let user = self.user()?;
+                let game = self.game()?;
+                game.suggest_item(user, &text)?;
+                self.games_manager.save_game(game);
+                Ok(None)
+            },
+            ClientEvent::ClaimBingo => {
+                let user = self.user()?;
+                let game = self.game()?;
+                game.claim_bingo(user)?;
+                self.games_manager.save_game(game);
+                Ok(None)
+            },
+            ClientEvent::PickItem { idx } => {
+                let game = self.authorized_game(Permission::RunBoard)?;
+                game.pick_item(idx)?;
+                self.games_manager.save_game(game);
+                Ok(None)
+            },
+            ClientEvent::UnpickItem { idx } => {
+                let game = self.authorized_game(Permission::RunBoard)?;
+                game.unpick_item(idx)?;
+                self.games_manager.save_game(game);
+                Ok(None)
+            },
+            ClientEvent::ReplacePick { old, new } => {
+                let game = self.authorized_game(Permission::RunBoard)?;
+                game.replace_pick(old, new)?;
+                self.games_manager.save_game(game);
+                Ok(None)
+            },
+            ClientEvent::OpenPoll { idx } => {
+                let game = self.authorized_game(Permission::RunBoard)?;
+                self.games_manager.open_poll(game, idx)?;
+                Ok(None)
+            },
+            ClientEvent::Vote { poll, yes } => {
+                let user = self.user()?;
+                let game = self.game()?;
+                if game.vote(user, poll, yes)? {
+                    self.games_manager.save_game(game);
+                }
+                Ok(None)
+            },
+            ClientEvent::NewRound => {
+                let game = self.authorized_game(Permission::Host)?;
+                let players = game.new_round()?;
+                self.games_manager.save_players(game, players);
+                Ok(None)
+            },
+            // answered with a pong by the caller
+            ClientEvent::Ping => Ok(None),
+            ClientEvent::Subscribe { game, key } => {
+                let game = self.games_manager.find_game(&game).ok_or(GameError::NoSuchGame)?;
+                game.check_access(self.claims.as_ref(), key.as_deref())?;
+                let watching = self.spectator.as_ref().is_some_and(|s| Arc::ptr_eq(s.game(), &game));
+                if self.spectating && !watching {
+                    // this gives back the spot in the previous game
+                    self.spectator = Some(self.games_manager.spectate(&game)?);
+                }
+                self.subscribe(game, ctx);
+                Ok(None)
+            },
+        }
+    }
+
+    fn handle_message(&mut self, text: &str, ctx: &mut <Self as Actor>::Context) -> Reply {
+        let ClientMessage { id, event } = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(msg) => msg,
+            Err(e) => {
+                // the id is still worth replying with if it can be found
+                let id = serde_json::from_str::<serde_json::Value>(text).ok()
+                    .and_then(|v| v.get("id").and_then(serde_json::Value::as_u64));
+                return Reply::Rejected { id, error: ProtocolError::Malformed { reason: e.to_string() }.into() };
+            },
+        };
+        let is_ping = matches!(event, ClientEvent::Ping);
+        match self.handle_event(event, ctx) {
+            Ok(_) if is_ping => Reply::Pong { id },
+            Ok(board) => Reply::Ack { id, board },
+            Err(error) => Reply::Rejected { id, error },
+        }
+    }
+
+    fn send_reply(&self, reply: &Reply, ctx: &mut <Self as Actor>::Context) {
+        match serde_json::to_string(reply) {
+            Ok(json) => ctx.text(json),
+            Err(e) => error!("failed to serialize reply: {e}"),
+        }
+    }
 }
 
 impl Actor for BingoWs {
@@ -42,7 +203,9 @@ impl Actor for BingoWs {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         self.heartbeat_handle = Some(ctx.spawn(Heartbeat::new(HEARTBEAT_TIME, Duration::from_secs(1))));
-        self.listener_handle = Some(ctx.spawn(EventListener::new(self.listener.take().unwrap())));
+        if let Some(game) = self.game.clone() {
+            self.subscribe(game, ctx);
+        }
     }
 
     // these are both cancel-safe since no important data is stored in them
@@ -64,14 +227,12 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for BingoWs {
 
         match msg {
             Ok(ws::Message::Text(text)) => {
-                match serde_json::from_str::<ClientEvent>(&text) {
-                    Ok(o) => {
-                        todo!("handle received WS messages")
-                    },
-                    Err(e) => {
-                        todo!("implement handling for deserializing received WS messages")
-                    },
-                }
+                let reply = self.handle_message(&text, ctx);
+                self.send_reply(&reply, ctx);
+            },
+            Ok(ws::Message::Binary(_)) => {
+                let reply = Reply::Rejected { id: None, error: ProtocolError::UnsupportedFrame.into() };
+                self.send_reply(&reply, ctx);
             },
             Ok(ws::Message::Close(_)) => ctx.stop(),
             Ok(_) => (),
@@ -83,8 +244,14 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for BingoWs {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "swagger-ui", derive(utoipa::IntoParams))]
 pub struct WsParams {
-    /// the ULID of the game the client wishes to monitor
-    game: Ulid
+    /// the ULID or join code of the game the client wishes to monitor,
+    /// clients can also subscribe to one after connecting, which is how
+    /// browsers pass the key of a private game since they can't set headers
+    game: Option<String>,
+    /// only watch games, which needs no login and doesn't count as a player,
+    /// clients that aren't logged in always only watch
+    #[serde(default)]
+    spectate: bool,
 }
 
 #[derive(Debug, Clone, Serialize, thiserror::Error)]
@@ -93,11 +260,19 @@ pub struct WsParams {
 pub enum WsRequestError {
     #[error("there is no such game")]
     NoSuchGame,
+    #[error("this game is private, watching it takes its invite code or password")]
+    Private,
+    #[error("this game has as many spectators as it can take")]
+    TooManySpectators,
 }
 
 impl ResponseError for WsRequestError {
     fn status_code(&self) -> actix_web::http::StatusCode {
-        actix_web::http::StatusCode::BAD_REQUEST
+        match self {
+            WsRequestError::NoSuchGame => actix_web::http::StatusCode::BAD_REQUEST,
+            WsRequestError::Private => actix_web::http::StatusCode::FORBIDDEN,
+            WsRequestError::TooManySpectators => actix_web::http::StatusCode::CONFLICT,
+        }
     }
 
     fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
@@ -105,29 +280,54 @@ impl ResponseError for WsRequestError {
     }
 }
 
-/// websocket connection to give live game updates to players
+/// websocket connection to give live game updates to players, or to
+/// spectators who only see picks, winners and state changes
 #[cfg_attr(feature = "swagger-ui", utoipa::path(
     get,
     path = "/ws",
     tag = "Game",
-    params(WsParams),
+    params(WsParams, AccessKey),
     responses(
         (status = 101, description = "Upgrading connection to a websocket"),
-        (status = 400, description = "Game wasn't found", body = WsRequestError)
+        (status = 400, description = "Game wasn't found", body = WsRequestError),
+        (status = 403, description = "The game is private and the key was missing or wrong", body = WsRequestError),
+        (status = 409, description = "The game has as many spectators as it can take", body = WsRequestError)
     )
 ))]
 pub async fn websocket(
     req: HttpRequest,
     stream: web::Payload,
     params: Query<WsParams>,
+    key: AccessKey,
+    claims: Option<Claims>,
     games_manager: Data<GamesManager>
 ) -> impl Responder {
-    if let Some(rx) = games_manager.get_game(params.game).map(|g| g.subscribe_to()) {
-        let ws = BingoWs::new(rx, params.game);
-        let resp = ws::start(ws, &req, stream);
-        info!("{resp:?}");
-        resp
-    } else {
-        Err(Error::from(WsRequestError::NoSuchGame))
-    }
+    let game = match &params.game {
+        Some(id) => match games_manager.find_game(id) {
+            Some(game) if game.check_access(claims.as_ref(), key.0.as_deref()).is_err() => {
+                return Err(Error::from(WsRequestError::Private));
+            },
+            Some(game) => Some(game),
+            None => return Err(Error::from(WsRequestError::NoSuchGame)),
+        },
+        None => None,
+    };
+    // without a login there's nothing to do but watch, so those clients take
+    // up a spectator spot like any other
+    let spectating = params.spectate || claims.is_none();
+    let spectator = match (&game, spectating) {
+        (Some(game), true) => match games_manager.spectate(game) {
+            Ok(spectator) => Some(spectator),
+            Err(_) => return Err(Error::from(WsRequestError::TooManySpectators)),
+        },
+        _ => None,
+    };
+    let ws = BingoWs::new(game, games_manager, claims);
+    let ws = match spectating {
+        true => ws.with_spectator(spectator),
+        false => ws,
+    };
+    let resp = ws::start(ws, &req, stream);
+    info!("{resp:?}");
+    resp
 }
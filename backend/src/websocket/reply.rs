@@ -0,0 +1,56 @@
+use serde::Serialize;
+
+use crate::game::{board::BoardData, error::GameError};
+
+/// the answer to a [ClientMessage](crate::event::ClientMessage), only sent to
+/// the client that sent it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+    /// the request succeeded
+    Ack {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        /// the player's board, in answer to joining a game that already
+        /// started
+        #[serde(skip_serializing_if = "Option::is_none")]
+        board: Option<BoardData>,
+    },
+    Pong {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    /// the request failed, nothing was changed
+    Rejected {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+        #[serde(flatten)]
+        error: ReplyError,
+    },
+}
+
+/// why a request was rejected, either tagged as `error`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(untagged)]
+pub enum ReplyError {
+    #[error(transparent)]
+    Game(#[from] GameError),
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+}
+
+/// errors about the websocket messages themselves, rather than the game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(rename_all = "snake_case", tag = "error")]
+pub enum ProtocolError {
+    #[error("the message couldn't be parsed: {reason}")]
+    Malformed {
+        reason: String,
+    },
+    #[error("only text messages are supported")]
+    UnsupportedFrame,
+    #[error("subscribe to a game first")]
+    NotSubscribed,
+    #[error("spectators can only subscribe to games")]
+    Spectating,
+}
@@ -0,0 +1,42 @@
+#![allow(clippy::unwrap_used)]
+
+use ulid::Ulid;
+
+use crate::{event::{ClientEvent, ClientMessage}, game::error::GameError};
+
+use super::reply::{ProtocolError, Reply};
+
+#[test]
+fn test_client_messages() {
+    let msg: ClientMessage = serde_json::from_str(r#"{"id": 4, "type": "pick_item", "idx": 7}"#).unwrap();
+    assert_eq!(msg.id, Some(4));
+    assert!(matches!(msg.event, ClientEvent::PickItem { idx: 7 }));
+
+    let msg: ClientMessage = serde_json::from_str(r#"{"type": "ping"}"#).unwrap();
+    assert_eq!(msg.id, None);
+    assert!(matches!(msg.event, ClientEvent::Ping));
+
+    let game = Ulid::new();
+    let msg: ClientMessage = serde_json::from_str(&format!(r#"{{"id": 1, "type": "subscribe", "game": "{game}"}}"#)).unwrap();
+    assert!(matches!(msg.event, ClientEvent::Subscribe { game: g, key: None } if g == game.to_string()));
+
+    assert!(serde_json::from_str::<ClientMessage>(r#"{"id": 1, "type": "explode"}"#).is_err());
+    assert!(serde_json::from_str::<ClientMessage>(r#"{"id": 1, "type": "mark_cell"}"#).is_err());
+}
+
+#[test]
+fn test_replies() {
+    let reply = Reply::Rejected { id: Some(3), error: GameError::AlreadyPicked.into() };
+    assert_eq!(serde_json::to_value(&reply).unwrap(), serde_json::json!({
+        "rejected": { "id": 3, "error": "already_picked" }
+    }));
+
+    let reply = Reply::Rejected { id: None, error: ProtocolError::Malformed { reason: "bad".into() }.into() };
+    assert_eq!(serde_json::to_value(&reply).unwrap(), serde_json::json!({
+        "rejected": { "error": "malformed", "reason": "bad" }
+    }));
+
+    let reply = Reply::Ack { id: Some(1), board: None };
+    assert_eq!(serde_json::to_value(&reply).unwrap(), serde_json::json!({ "ack": { "id": 1 } }));
+    assert_eq!(serde_json::to_value(Reply::Pong { id: Some(2) }).unwrap(), serde_json::json!({ "pong": { "id": 2 } }));
+}